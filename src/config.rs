@@ -0,0 +1,283 @@
+use anyhow::Context;
+use dirs::config_dir;
+use log::debug;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::TimeDelta;
+
+use crate::framer::{
+    camera::CameraAlias,
+    date_time::{CameraTimeShift, TimeSpan, Timezone, DEFAULT_DATE_FORMAT},
+    fields::CustomField,
+    focal_length::{CameraCropFactor, FocalLengthDisplay},
+    formatter::{ApertureFormat, IsoFormat, ShutterSpeedFormat},
+    gps::GpsFormat,
+    iso::CameraNativeIso,
+    layout::DisplayOrders,
+    lens_database::LensId,
+    missing_value::MissingValue,
+    overrides::normalize_key,
+    positions::Overflow,
+    profile::FilmProfile,
+};
+
+/// User configuration read from `config.toml` in the metaframer configuration directory
+///
+/// Every option is optional, command line arguments take precedence over the file.
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Placeholder text displayed when the value is missing in the metadata,
+    /// `hide` leaves the value out and `error` fails the image
+    pub missing_value: MissingValue,
+    /// Missing value policies of the fields like `lens` overriding `missing-value`
+    pub missing_values: BTreeMap<String, MissingValue>,
+    /// Directory with the fonts used by the templates, defaults to `fonts` in the configuration directory
+    pub fonts: Option<PathBuf>,
+    /// Handling of the values that don't fit into the width of the frame
+    pub overflow: Overflow,
+    /// Rows of the frame with their left, center and right groups
+    #[serde(rename = "row")]
+    pub rows: Vec<DisplayOrders>,
+    /// Fields displayed from the left edge of the frame, replaces the ones chosen by the `show-*` options
+    pub left: Option<Vec<String>>,
+    /// Fields displayed in the middle of the frame, replaces the signature
+    pub center: Option<Vec<String>>,
+    /// Fields displayed up to the right edge of the frame, replaces the exposure values
+    pub right: Option<Vec<String>>,
+    pub gps_format: GpsFormat,
+    pub shutter_speed_format: ShutterSpeedFormat,
+    pub aperture_format: ApertureFormat,
+    pub iso_format: IsoFormat,
+    /// Adds "(ext)" to ISO speeds outside of the native range of the camera
+    pub mark_extended_iso: bool,
+    /// Native ISO ranges of the cameras used for `mark-extended-iso`
+    #[serde(rename = "camera-native-iso")]
+    pub camera_native_isos: Vec<CameraNativeIso>,
+    /// Displays GPS coordinates, altitude and direction next to the camera
+    pub show_gps: bool,
+    /// Displays the place name resolved from GPS coordinates next to the camera
+    pub show_location: bool,
+    /// GeoNames `cities*.txt` dump used instead of the bundled list of major cities
+    pub geonames: Option<PathBuf>,
+    /// TOML file with named places, defaults to `places.toml` in the configuration directory
+    pub places: Option<PathBuf>,
+    /// Maximum distance in kilometers to the nearest city to be used as location
+    pub geocoding_max_distance: f64,
+    /// Displays the capture date and time next to the camera
+    pub show_date: bool,
+    /// strftime-like format of the capture date
+    pub date_format: String,
+    /// Timezone used when the image doesn't store the offset of the capture time
+    pub timezone: Option<Timezone>,
+    /// Shift of the capture time for all images resolved from `--time-shift`
+    #[serde(skip)]
+    pub time_shift: Option<TimeDelta>,
+    /// Shifts of the capture time for the cameras with wrongly set clock
+    #[serde(rename = "camera-time-shift")]
+    pub camera_time_shifts: Vec<CameraTimeShift>,
+    /// Maximum time between GPX track points (and to the nearest point) to be used for the image
+    pub gpx_max_gap: TimeSpan,
+    /// Difference between the camera clock and GPX track time
+    pub gpx_time_offset: TimeSpan,
+    /// Displays the actual, the 35mm-equivalent focal length or both of them
+    pub focal_length_display: FocalLengthDisplay,
+    /// Displays the 35mm-equivalent aperture next to the actual one
+    pub show_equivalent_aperture: bool,
+    /// Displays exposure compensation, exposure program, metering mode, flash and white balance
+    pub show_exposure: bool,
+    /// Displays the film simulation or picture style and the shutter count from the maker note
+    pub show_maker_note: bool,
+    /// Displays the signature in the middle of the frame
+    pub show_signature: bool,
+    /// Signature with `{artist}`, `{copyright}` and `{year}` placeholders, the copyright notice
+    /// or `© {year} {artist}` is used by default
+    pub signature: Option<String>,
+    /// Display names of the cameras replacing the normalized make and model
+    #[serde(rename = "camera-alias")]
+    pub camera_aliases: Vec<CameraAlias>,
+    /// Crop factors of the cameras that don't store the 35mm-equivalent focal length
+    #[serde(rename = "camera-crop-factor")]
+    pub camera_crop_factors: Vec<CameraCropFactor>,
+    /// Lens names for the lens IDs missing in the bundled list
+    #[serde(rename = "lens-id")]
+    pub lens_ids: Vec<LensId>,
+    /// Fields read from EXIF, XMP, IPTC or the maker note in addition to the built-in ones
+    #[serde(rename = "field")]
+    pub custom_fields: Vec<CustomField>,
+    /// Name of the film profile used instead of the scanner's EXIF
+    pub profile: Option<String>,
+    /// Camera, lens, film stock and ISO of the film shots
+    #[serde(rename = "film-profile")]
+    pub film_profiles: Vec<FilmProfile>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            missing_value: MissingValue::Placeholder("N/A".to_string()),
+            missing_values: BTreeMap::new(),
+            fonts: None,
+            overflow: Overflow::default(),
+            rows: vec![],
+            left: None,
+            center: None,
+            right: None,
+            gps_format: GpsFormat::default(),
+            shutter_speed_format: ShutterSpeedFormat::default(),
+            aperture_format: ApertureFormat::default(),
+            iso_format: IsoFormat::default(),
+            mark_extended_iso: false,
+            camera_native_isos: vec![],
+            show_gps: false,
+            show_location: false,
+            geonames: None,
+            places: None,
+            geocoding_max_distance: 50.,
+            show_date: false,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            timezone: None,
+            time_shift: None,
+            camera_time_shifts: vec![],
+            gpx_max_gap: TimeSpan(TimeDelta::minutes(5)),
+            gpx_time_offset: TimeSpan(TimeDelta::zero()),
+            focal_length_display: FocalLengthDisplay::default(),
+            show_equivalent_aperture: false,
+            show_exposure: false,
+            show_maker_note: false,
+            show_signature: false,
+            signature: None,
+            camera_aliases: vec![],
+            camera_crop_factors: vec![],
+            lens_ids: vec![],
+            custom_fields: vec![],
+            profile: None,
+            film_profiles: vec![],
+        }
+    }
+}
+
+pub fn get_config_path() -> PathBuf {
+    let config_dir = config_dir().unwrap();
+    Path::new(&config_dir).join("metaframer/config.toml")
+}
+
+pub fn load_config() -> Result<Config, anyhow::Error> {
+    let config_path = get_config_path();
+    if !config_path.exists() {
+        debug!("{:?} doesn't exist, using default config", config_path);
+        return Ok(Config::default());
+    }
+    let content = fs::read_to_string(&config_path)
+        .with_context(|| format!("could not read config file `{:?}`", config_path))?;
+    parse_config(&content).with_context(|| format!("invalid config file `{:?}`", config_path))
+}
+
+pub fn parse_config(content: &str) -> Result<Config, anyhow::Error> {
+    let mut config: Config = toml::from_str(content)?;
+    // Looked up by the field names which are normalized like in the layout
+    config.missing_values = config
+        .missing_values
+        .into_iter()
+        .map(|(name, missing_value)| (normalize_key(&name), missing_value))
+        .collect();
+    Ok(config)
+}
+
+#[test]
+fn test_parse_config() {
+    let config = parse_config(
+        r#"
+        missing-value = "-"
+        gps-format = "compass"
+        aperture-format = "hook"
+        show-gps = true
+        timezone = "Europe/Bratislava"
+        right = ["aperture", "iso"]
+
+        [missing-values]
+        lens = "hide"
+        iso = "error"
+        shutter-count = "-"
+
+        [[camera-time-shift]]
+        make = "Canon"
+        model = "Canon EOS R5"
+        shift = "-1h"
+
+        [[camera-crop-factor]]
+        make = "OM Digital Solutions"
+        model = "OM-1"
+        crop-factor = 2.0
+
+        [[row]]
+        left = ["camera", "lens"]
+
+        [[row]]
+        right = ["aperture", "iso"]
+
+        [[lens-id]]
+        vendor = "Canon"
+        id = 368
+        name = "Sigma 14-24mm f/2.8 DG HSM | A"
+
+        [[film-profile]]
+        name = "Pentax 67 + Portra 400"
+        camera = "Pentax 67"
+        film-stock = "Kodak Portra 400"
+        iso = 400
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.missing_value,
+        MissingValue::Placeholder("-".to_string())
+    );
+    assert_eq!(config.missing_values["lens"], MissingValue::Hide);
+    assert_eq!(config.missing_values["iso"], MissingValue::Error);
+    assert_eq!(
+        config.missing_values["shutter_count"],
+        MissingValue::Placeholder("-".to_string())
+    );
+    assert_eq!(config.gps_format, GpsFormat::Compass);
+    assert_eq!(config.aperture_format, ApertureFormat::Hook);
+    assert!(config.show_gps);
+    assert_eq!(
+        config.right,
+        Some(vec!["aperture".to_string(), "iso".to_string()])
+    );
+    assert_eq!(config.left, None);
+    assert_eq!(config.rows.len(), 2);
+    assert_eq!(config.rows[1].right, vec!["aperture", "iso"]);
+    assert!(config.rows[1].left.is_empty());
+    assert_eq!(
+        config.timezone,
+        Some(Timezone::Named(chrono_tz::Europe::Bratislava))
+    );
+    assert_eq!(
+        config.camera_time_shifts[0].shift.0,
+        TimeDelta::try_hours(-1).unwrap()
+    );
+
+    assert_eq!(config.camera_crop_factors[0].crop_factor, 2.0);
+    assert_eq!(
+        config.film_profiles[0].film_stock,
+        Some("Kodak Portra 400".to_string())
+    );
+    assert_eq!(config.film_profiles[0].lens, None);
+    assert_eq!(
+        config.lens_ids[0].vendor,
+        crate::framer::makernote::Vendor::Canon
+    );
+
+    let config = parse_config("").unwrap();
+    assert_eq!(
+        config.missing_value,
+        MissingValue::Placeholder("N/A".to_string())
+    );
+    assert_eq!(config.gps_format, GpsFormat::Dms);
+    assert!(!config.show_gps);
+}
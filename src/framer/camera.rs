@@ -0,0 +1,166 @@
+use exif::{Exif, Tag};
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+use super::text_values::get_string;
+
+/// Nikon marks the second and third generation with `_2` and `_3`
+static GENERATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"_(2|3)$").unwrap());
+
+/// Entry of a config table that applies to the camera with the matching `Make` and `Model`
+pub trait CameraEntry {
+    fn camera(&self) -> (&str, &str);
+}
+
+/// Display name for the raw `Make` and `Model` pair
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CameraAlias {
+    pub make: String,
+    pub model: String,
+    pub name: String,
+}
+
+impl CameraEntry for CameraAlias {
+    fn camera(&self) -> (&str, &str) {
+        (&self.make, &self.model)
+    }
+}
+
+/// Brands whose name can't be derived by stripping the suffixes and fixing the capitalization
+const BRANDS: [(&str, &str); 3] = [
+    ("OM DIGITAL SOLUTIONS", "OM System"),
+    ("LGE", "LG"),
+    ("GOPRO", "GoPro"),
+];
+
+/// Words appended by vendors to the `Make` that are not part of the brand
+const MAKE_SUFFIXES: [&str; 9] = [
+    "CORPORATION",
+    "CORP.",
+    "IMAGING",
+    "COMPANY,",
+    "COMPANY",
+    "CO.,",
+    "LTD.",
+    "CAMERA AG",
+    "AG",
+];
+
+/// Entry for the camera that took the image, both compared case-insensitively
+pub fn find_camera_entry<'a, T: CameraEntry>(exif: &Exif, entries: &'a [T]) -> Option<&'a T> {
+    let make = get_string(exif, Tag::Make)?;
+    let model = get_string(exif, Tag::Model)?;
+    find_entry(&make, &model, entries)
+}
+
+fn find_entry<'a, T: CameraEntry>(make: &str, model: &str, entries: &'a [T]) -> Option<&'a T> {
+    entries.iter().find(|entry| {
+        let (entry_make, entry_model) = entry.camera();
+        entry_make.trim().eq_ignore_ascii_case(make.trim())
+            && entry_model.trim().eq_ignore_ascii_case(model.trim())
+    })
+}
+
+/// Camera name from the alias table or the normalized make and model
+pub fn get_camera_name(make: &str, model: &str, aliases: &[CameraAlias]) -> String {
+    if let Some(alias) = find_entry(make, model, aliases) {
+        return alias.name.clone();
+    }
+
+    let brand = normalize_make(make);
+    let model = normalize_model(make, &brand, model);
+    match model.is_empty() {
+        true => brand,
+        false => format!("{} {}", brand, model),
+    }
+}
+
+/// "NIKON CORPORATION" -> "Nikon", "OLYMPUS IMAGING CORP." -> "Olympus"
+pub fn normalize_make(make: &str) -> String {
+    let make = make.trim();
+    if let Some((_, brand)) = BRANDS
+        .iter()
+        .find(|(raw, _)| raw.eq_ignore_ascii_case(make))
+    {
+        return brand.to_string();
+    }
+
+    let mut brand = make.to_string();
+    while let Some(suffix) = MAKE_SUFFIXES.iter().find(|suffix| {
+        brand.len() > suffix.len()
+            && brand
+                .to_ascii_uppercase()
+                .ends_with(&format!(" {}", suffix))
+    }) {
+        brand.truncate(brand.len() - suffix.len() - 1);
+        brand = brand.trim_end().to_string();
+    }
+
+    brand
+        .split_whitespace()
+        .map(capitalize)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Removes the brand repeated in the model, "Canon EOS R5" -> "EOS R5", "NIKON Z 6_2" -> "Z 6II"
+pub fn normalize_model(make: &str, brand: &str, model: &str) -> String {
+    let mut model = model.trim();
+    for prefix in [make.trim(), brand] {
+        if model.len() > prefix.len()
+            && model.is_char_boundary(prefix.len())
+            && model[..prefix.len()].eq_ignore_ascii_case(prefix)
+        {
+            model = model[prefix.len()..].trim_start();
+        }
+    }
+    GENERATION
+        .replace(model, |captures: &regex::Captures| {
+            match &captures[1] {
+                "2" => "II",
+                _ => "III",
+            }
+            .to_string()
+        })
+        .to_string()
+}
+
+/// Upper case words longer than an abbreviation are turned to title case
+fn capitalize(word: &str) -> String {
+    let is_upper_case = word.chars().all(|c| !c.is_lowercase());
+    if !is_upper_case || word.chars().filter(|c| c.is_alphabetic()).count() <= 3 {
+        return word.to_string();
+    }
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+        None => String::new(),
+    }
+}
+
+#[test]
+fn test_get_camera_name() {
+    let camera = |make, model| get_camera_name(make, model, &[]);
+
+    assert_eq!(camera("NIKON CORPORATION", "NIKON Z 6_2"), "Nikon Z 6II");
+    assert_eq!(camera("Canon", "Canon EOS R5"), "Canon EOS R5");
+    assert_eq!(camera("FUJIFILM", "X-T30"), "Fujifilm X-T30");
+    assert_eq!(camera("OLYMPUS IMAGING CORP.", "E-M5"), "Olympus E-M5");
+    assert_eq!(camera("OM Digital Solutions", "OM-1"), "OM System OM-1");
+    assert_eq!(
+        camera("RICOH IMAGING COMPANY, LTD.", "PENTAX K-3 Mark III"),
+        "Ricoh PENTAX K-3 Mark III"
+    );
+    assert_eq!(camera("SONY", "ILCE-7M3"), "Sony ILCE-7M3");
+    assert_eq!(camera("DJI", "FC3582"), "DJI FC3582");
+    assert_eq!(camera("Apple", "iPhone 15 Pro"), "Apple iPhone 15 Pro");
+    assert_eq!(camera("LEICA CAMERA AG", "LEICA Q2"), "Leica Q2");
+
+    let aliases = [CameraAlias {
+        make: "SONY".to_string(),
+        model: "ILCE-7M3".to_string(),
+        name: "Sony α7 III".to_string(),
+    }];
+    assert_eq!(get_camera_name("SONY", "ILCE-7M3", &aliases), "Sony α7 III");
+}
@@ -0,0 +1,347 @@
+use std::{fmt::Write, str::FromStr};
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeDelta, TimeZone};
+use chrono_tz::Tz;
+use exif::{Exif, In, Tag, Value};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+use super::camera::{find_camera_entry, CameraEntry};
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Local time of the capture with the offset if it could be determined
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DateTaken {
+    pub local: NaiveDateTime,
+    pub offset: Option<FixedOffset>,
+}
+
+/// Timezone used for dates without the `OffsetTimeOriginal` tag
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Timezone {
+    /// Fixed offset like `+02:00`
+    Fixed(FixedOffset),
+    /// IANA timezone like `Europe/Bratislava` respecting the daylight saving time
+    Named(Tz),
+}
+
+impl FromStr for Timezone {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .parse::<FixedOffset>()
+            .map(Timezone::Fixed)
+            .or_else(|_| value.parse::<Tz>().map(Timezone::Named))
+            .map_err(|_| {
+                format!(
+                    "`{}` is neither an offset (+02:00) nor a timezone name",
+                    value
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Timezone {
+    pub fn get_offset(&self, local: &NaiveDateTime) -> Option<FixedOffset> {
+        match self {
+            Timezone::Fixed(offset) => Some(*offset),
+            Timezone::Named(tz) => tz
+                .from_local_datetime(local)
+                .earliest()
+                .map(|date_time| date_time.fixed_offset().timezone()),
+        }
+    }
+}
+
+/// Signed span of time like `+1h23m`, `-1d2h` or `5m`
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TimeSpan(pub TimeDelta);
+
+/// Value of `--time-shift`, either a fixed offset or the time when the first image was actually taken
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TimeShift {
+    Offset(TimeSpan),
+    ActualTime(NaiveDateTime),
+}
+
+/// Clock offset applied only to the images taken by the matching camera
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CameraTimeShift {
+    pub make: String,
+    pub model: String,
+    pub shift: TimeSpan,
+}
+
+impl CameraEntry for CameraTimeShift {
+    fn camera(&self) -> (&str, &str) {
+        (&self.make, &self.model)
+    }
+}
+
+impl FromStr for TimeSpan {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let regexp = Regex::new(r"^([+-])?(?:(\d+)d)?(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$").unwrap();
+        let error = || format!("`{}` is not a valid time shift like +1h23m", value);
+        let captures = regexp
+            .captures(value.trim())
+            .filter(|captures| (2..=5).any(|index| captures.get(index).is_some()))
+            .ok_or_else(error)?;
+
+        let get_number = |index| {
+            captures
+                .get(index)
+                .map_or(Ok(0), |number| number.as_str().parse::<i64>())
+                .map_err(|_| error())
+        };
+        // Spans too long for `TimeDelta` are rejected instead of overflowing
+        let parts = [
+            TimeDelta::try_days(get_number(2)?),
+            TimeDelta::try_hours(get_number(3)?),
+            TimeDelta::try_minutes(get_number(4)?),
+            TimeDelta::try_seconds(get_number(5)?),
+        ];
+        let span = parts
+            .into_iter()
+            .try_fold(TimeDelta::zero(), |span, part| span.checked_add(&part?))
+            .ok_or_else(error)?;
+        match captures.get(1).map(|sign| sign.as_str()) {
+            Some("-") => Ok(TimeSpan(-span)),
+            _ => Ok(TimeSpan(span)),
+        }
+    }
+}
+
+impl TryFrom<String> for TimeSpan {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl FromStr for TimeShift {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(offset) = value.parse::<TimeSpan>() {
+            return Ok(TimeShift::Offset(offset));
+        }
+        [
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y:%m:%d %H:%M:%S",
+            "%Y-%m-%d %H:%M",
+        ]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+        .map(TimeShift::ActualTime)
+        .ok_or_else(|| {
+            format!(
+                "`{}` is neither a time shift (+1h23m) nor a date and time (2024-07-14 18:05:00)",
+                value
+            )
+        })
+    }
+}
+
+/// Picks the shift from `--time-shift` or the one configured for the camera that took the image
+pub fn get_time_shift(config: &Config, exif: &Exif) -> Option<TimeDelta> {
+    if config.time_shift.is_some() {
+        return config.time_shift;
+    }
+    find_camera_entry(exif, &config.camera_time_shifts).map(|camera| camera.shift.0)
+}
+
+impl DateTaken {
+    /// Shifted capture time, `None` if it would be out of the supported range
+    pub fn shift(self, time_shift: Option<TimeDelta>) -> Option<DateTaken> {
+        match time_shift {
+            Some(time_shift) => Some(DateTaken {
+                local: self.local.checked_add_signed(time_shift)?,
+                offset: self.offset,
+            }),
+            None => Some(self),
+        }
+    }
+
+    pub fn to_date_time(self) -> Option<DateTime<FixedOffset>> {
+        self.offset?.from_local_datetime(&self.local).single()
+    }
+
+    /// ISO 8601 representation passed to the templates, without offset if it is unknown
+    pub fn to_iso_string(self) -> String {
+        match self.to_date_time() {
+            Some(date_time) => date_time.to_rfc3339(),
+            None => self.local.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        }
+    }
+}
+
+pub fn get_date_taken(exif: &Exif, timezone: Option<&Timezone>) -> Option<DateTaken> {
+    let mut date_time = exif::DateTime::from_ascii(get_ascii(exif, Tag::DateTimeOriginal)?).ok()?;
+    if let Some(subsec) = get_ascii(exif, Tag::SubSecTimeOriginal) {
+        date_time.parse_subsec(subsec).ok();
+    }
+    if let Some(offset) = get_ascii(exif, Tag::OffsetTimeOriginal) {
+        date_time.parse_offset(offset).ok();
+    }
+
+    let local = to_naive_date_time(&date_time)?;
+    let offset = match date_time.offset {
+        Some(minutes) => FixedOffset::east_opt(i32::from(minutes) * 60),
+        None => timezone.and_then(|timezone| timezone.get_offset(&local)),
+    };
+
+    Some(DateTaken { local, offset })
+}
+
+pub fn to_naive_date_time(date_time: &exif::DateTime) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(
+        date_time.year.into(),
+        date_time.month.into(),
+        date_time.day.into(),
+    )?
+    .and_hms_nano_opt(
+        date_time.hour.into(),
+        date_time.minute.into(),
+        date_time.second.into(),
+        date_time.nanosecond.unwrap_or(0),
+    )
+}
+
+fn get_ascii(exif: &Exif, tag: Tag) -> Option<&[u8]> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first().map(|value| value.as_slice()),
+        _ => None,
+    }
+}
+
+/// Formats the date with strftime-like format, `None` if the format is invalid
+/// or requires the offset that is unknown
+pub fn format_date_taken(date: &DateTaken, format: &str) -> Option<String> {
+    let mut formatted = String::new();
+    let result = match date.to_date_time() {
+        Some(date_time) => write!(formatted, "{}", date_time.format(format)),
+        None => write!(formatted, "{}", date.local.format(format)),
+    };
+    result.ok().map(|_| formatted)
+}
+
+/// Parses the ISO string produced by `DateTaken::to_iso_string`
+pub fn parse_iso_string(value: &str) -> Option<DateTaken> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(date_time) => Some(DateTaken {
+            local: date_time.naive_local(),
+            offset: Some(date_time.timezone()),
+        }),
+        Err(_) => NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+            .ok()
+            .map(|local| DateTaken {
+                local,
+                offset: None,
+            }),
+    }
+}
+
+#[test]
+fn test_format_date_taken() {
+    let local = NaiveDate::from_ymd_opt(2024, 7, 14)
+        .unwrap()
+        .and_hms_milli_opt(18, 5, 9, 120)
+        .unwrap();
+    let date = DateTaken {
+        local,
+        offset: FixedOffset::east_opt(2 * 3600),
+    };
+
+    assert_eq!(
+        format_date_taken(&date, DEFAULT_DATE_FORMAT),
+        Some("2024-07-14 18:05".to_string())
+    );
+    assert_eq!(
+        format_date_taken(&date, "%d. %B %Y %H:%M:%S%.3f %:z"),
+        Some("14. July 2024 18:05:09.120 +02:00".to_string())
+    );
+    assert_eq!(date.to_iso_string(), "2024-07-14T18:05:09.120+02:00");
+    assert_eq!(parse_iso_string(&date.to_iso_string()), Some(date));
+
+    let naive = DateTaken {
+        local,
+        offset: None,
+    };
+    assert_eq!(format_date_taken(&naive, "%H:%M %z"), None);
+    assert_eq!(parse_iso_string(&naive.to_iso_string()), Some(naive));
+}
+
+#[test]
+fn test_time_shift() {
+    assert_eq!(
+        "+1h23m".parse(),
+        Ok(TimeShift::Offset(TimeSpan(TimeDelta::seconds(4980))))
+    );
+    assert_eq!(
+        "-1d30s".parse(),
+        Ok(TimeShift::Offset(TimeSpan(TimeDelta::seconds(-86430))))
+    );
+    assert_eq!(
+        "2024-07-14 18:05:00".parse(),
+        Ok(TimeShift::ActualTime(
+            NaiveDate::from_ymd_opt(2024, 7, 14)
+                .unwrap()
+                .and_hms_opt(18, 5, 0)
+                .unwrap()
+        ))
+    );
+    assert!("+".parse::<TimeShift>().is_err());
+    assert!("1 hour".parse::<TimeShift>().is_err());
+    assert!("+9999999999999999d".parse::<TimeShift>().is_err());
+
+    let date_taken = DateTaken {
+        local: NaiveDate::from_ymd_opt(2024, 7, 14)
+            .unwrap()
+            .and_hms_opt(18, 5, 0)
+            .unwrap(),
+        offset: None,
+    };
+    let TimeShift::Offset(TimeSpan(time_shift)) = "+100000000d".parse().unwrap() else {
+        panic!("expected an offset");
+    };
+    assert_eq!(date_taken.shift(Some(time_shift)), None);
+}
+
+#[test]
+fn test_timezone() {
+    let summer = NaiveDate::from_ymd_opt(2024, 7, 14)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+    let winter = NaiveDate::from_ymd_opt(2024, 1, 14)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+
+    let named: Timezone = "Europe/Bratislava".parse().unwrap();
+    assert_eq!(named.get_offset(&summer), FixedOffset::east_opt(2 * 3600));
+    assert_eq!(named.get_offset(&winter), FixedOffset::east_opt(3600));
+
+    let fixed: Timezone = "-05:00".parse().unwrap();
+    assert_eq!(fixed.get_offset(&summer), FixedOffset::west_opt(5 * 3600));
+
+    assert!("Mars/Olympus".parse::<Timezone>().is_err());
+}
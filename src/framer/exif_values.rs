@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use exif::{DateTime, Exif, Field, In, Tag, Value};
+use serde::Serialize;
+
+use super::date_time::to_naive_date_time;
+
+/// Single EXIF field exposed to the templates as `exif.<TagName>`
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ExifValue {
+    pub raw: RawValue,
+    pub display: String,
+    pub ifd: u16,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum RawValue {
+    Text(String),
+    Integer(i64),
+    Number(f64),
+    List(Vec<RawValue>),
+    None,
+}
+
+/// EXIF fields exposed to the templates, the primary image ones as `exif.<TagName>`
+/// and the ones of the other IFDs as `exif.thumbnail.<TagName>`
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct ExifValues {
+    #[serde(flatten)]
+    pub primary: BTreeMap<String, ExifValue>,
+    /// Fields of the IFDs other than the primary one keyed by `thumbnail` or `ifd<number>`
+    #[serde(flatten)]
+    pub other_ifds: BTreeMap<String, BTreeMap<String, ExifValue>>,
+}
+
+/// Collects every field of every IFD keyed by its tag name
+///
+/// The maker note is skipped as it is just an opaque blob of bytes.
+/// Date and time fields are corrected by the camera clock `time_shift`.
+pub fn get_exif_values(exif: &Exif, time_shift: Option<TimeDelta>) -> ExifValues {
+    let mut values = ExifValues::default();
+    let fields = exif.fields().filter(|field| field.tag != Tag::MakerNote);
+
+    for field in fields {
+        let value = match time_shift.and_then(|time_shift| shift_date_time(field, time_shift)) {
+            Some(shifted) => ExifValue {
+                raw: RawValue::Text(shifted.format("%Y:%m:%d %H:%M:%S").to_string()),
+                display: shifted.format("%Y-%m-%d %H:%M:%S").to_string(),
+                ifd: field.ifd_num.index(),
+            },
+            None => ExifValue {
+                raw: get_raw_value(&field.value),
+                display: field
+                    .display_value()
+                    .with_unit(exif)
+                    .to_string()
+                    .trim_matches('"')
+                    .to_string(),
+                ifd: field.ifd_num.index(),
+            },
+        };
+        let ifd_values = match field.ifd_num {
+            In::PRIMARY => &mut values.primary,
+            In::THUMBNAIL => values
+                .other_ifds
+                .entry("thumbnail".to_string())
+                .or_default(),
+            ifd_num => values
+                .other_ifds
+                .entry(format!("ifd{}", ifd_num.index()))
+                .or_default(),
+        };
+        ifd_values.entry(get_tag_name(field.tag)).or_insert(value);
+    }
+    values
+}
+
+fn shift_date_time(field: &Field, time_shift: TimeDelta) -> Option<NaiveDateTime> {
+    if ![Tag::DateTime, Tag::DateTimeOriginal, Tag::DateTimeDigitized].contains(&field.tag) {
+        return None;
+    }
+    let date_time = match &field.value {
+        Value::Ascii(values) => DateTime::from_ascii(values.first()?).ok()?,
+        _ => return None,
+    };
+    to_naive_date_time(&date_time)?.checked_add_signed(time_shift)
+}
+
+fn get_tag_name(tag: Tag) -> String {
+    match tag.description() {
+        Some(_) => tag.to_string(),
+        None => format!("{:?}{:#06x}", tag.context(), tag.number()),
+    }
+}
+
+pub fn get_raw_value(value: &Value) -> RawValue {
+    let values: Vec<RawValue> = match value {
+        Value::Ascii(texts) => texts
+            .iter()
+            .map(|text| RawValue::Text(String::from_utf8_lossy(text).trim().to_string()))
+            .collect(),
+        Value::Byte(numbers) => get_integers(numbers),
+        Value::Short(numbers) => get_integers(numbers),
+        Value::Long(numbers) => get_integers(numbers),
+        Value::SByte(numbers) => get_integers(numbers),
+        Value::SShort(numbers) => get_integers(numbers),
+        Value::SLong(numbers) => get_integers(numbers),
+        Value::Rational(numbers) => numbers.iter().map(|n| get_number(n.to_f64())).collect(),
+        Value::SRational(numbers) => numbers.iter().map(|n| get_number(n.to_f64())).collect(),
+        Value::Float(numbers) => numbers.iter().map(|n| get_number(*n as f64)).collect(),
+        Value::Double(numbers) => numbers.iter().map(|n| get_number(*n)).collect(),
+        Value::Undefined(..) | Value::Unknown(..) => vec![],
+    };
+
+    match values.len() {
+        0 => RawValue::None,
+        1 => values.into_iter().next().unwrap(),
+        _ => RawValue::List(values),
+    }
+}
+
+fn get_integers<T: Copy + Into<i64>>(numbers: &[T]) -> Vec<RawValue> {
+    numbers
+        .iter()
+        .map(|number| RawValue::Integer((*number).into()))
+        .collect()
+}
+
+// Division by zero in rationals would produce NaN that can't be serialized
+fn get_number(number: f64) -> RawValue {
+    if number.is_finite() {
+        RawValue::Number(number)
+    } else {
+        RawValue::None
+    }
+}
+
+#[test]
+fn test_get_raw_value() {
+    use exif::Rational;
+
+    assert_eq!(
+        get_raw_value(&Value::Ascii(vec![b"Canon".to_vec()])),
+        RawValue::Text("Canon".to_string())
+    );
+    assert_eq!(
+        get_raw_value(&Value::Short(vec![400])),
+        RawValue::Integer(400)
+    );
+    assert_eq!(
+        get_raw_value(&Value::Rational(vec![
+            Rational { num: 1, denom: 2 },
+            Rational { num: 1, denom: 0 }
+        ])),
+        RawValue::List(vec![RawValue::Number(0.5), RawValue::None])
+    );
+    assert_eq!(
+        get_raw_value(&Value::Undefined(b"0231".to_vec(), 0)),
+        RawValue::None
+    );
+}
+
+#[test]
+fn test_get_exif_values() {
+    use exif::{experimental::Writer, Rational};
+    use std::io::Cursor;
+
+    let resolution = |ifd_num, dpi| Field {
+        tag: Tag::XResolution,
+        ifd_num,
+        value: Value::Rational(vec![Rational { num: dpi, denom: 1 }]),
+    };
+    let fields = [
+        resolution(In::PRIMARY, 300),
+        resolution(In::THUMBNAIL, 72),
+        Field {
+            tag: Tag::Compression,
+            ifd_num: In::THUMBNAIL,
+            value: Value::Short(vec![6]),
+        },
+    ];
+    let mut writer = Writer::new();
+    fields.iter().for_each(|field| writer.push_field(field));
+    let mut tiff = Cursor::new(Vec::new());
+    writer.write(&mut tiff, false).unwrap();
+    let exif = exif::Reader::new().read_raw(tiff.into_inner()).unwrap();
+
+    let values = get_exif_values(&exif, None);
+    assert_eq!(values.primary["XResolution"].raw, RawValue::Number(300.));
+    assert!(!values.primary.contains_key("Compression"));
+    let thumbnail = &values.other_ifds["thumbnail"];
+    assert_eq!(thumbnail["XResolution"].raw, RawValue::Number(72.));
+    assert_eq!(thumbnail["Compression"].raw, RawValue::Integer(6));
+
+    let json = serde_json::to_value(&values).unwrap();
+    assert_eq!(json["thumbnail"]["XResolution"]["raw"], 72.);
+    assert_eq!(json["XResolution"]["raw"], 300.);
+}
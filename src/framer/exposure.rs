@@ -0,0 +1,122 @@
+use exif::{Exif, In, Tag, Value};
+
+use super::text_values::get_uint;
+
+/// Exposure compensation in thirds or halves of a stop: +⅓ EV, -1½ EV
+pub fn get_exposure_bias(exif: &Exif) -> Option<String> {
+    let bias = match &exif.get_field(Tag::ExposureBiasValue, In::PRIMARY)?.value {
+        Value::SRational(bias) => bias.first()?.to_f64(),
+        Value::Rational(bias) => bias.first()?.to_f64(),
+        _ => return None,
+    };
+    bias.is_finite().then(|| format_exposure_bias(bias))
+}
+
+pub fn format_exposure_bias(bias: f64) -> String {
+    // Sixths cover both the third and the half stop steps
+    let sixths = (bias * 6.).round();
+    if (bias * 6. - sixths).abs() > 0.1 {
+        return format!("{:+.1} EV", bias);
+    }
+    let sixths = sixths as i64;
+    if sixths == 0 {
+        return "0 EV".to_string();
+    }
+    let sign = if sixths > 0 { '+' } else { '-' };
+    let (stops, remainder) = (sixths.abs() / 6, sixths.abs() % 6);
+    let fraction = match remainder {
+        0 => "",
+        2 => "⅓",
+        3 => "½",
+        4 => "⅔",
+        _ => return format!("{:+.1} EV", bias),
+    };
+    match stops {
+        0 => format!("{}{} EV", sign, fraction),
+        _ => format!("{}{}{} EV", sign, stops, fraction),
+    }
+}
+
+pub fn get_metering_mode(exif: &Exif) -> Option<String> {
+    let mode = match get_uint(exif, Tag::MeteringMode)? {
+        1 => "Average",
+        2 => "Center-weighted",
+        3 => "Spot",
+        4 => "Multi-spot",
+        5 => "Multi-segment",
+        6 => "Partial",
+        _ => return None,
+    };
+    Some(mode.to_string())
+}
+
+pub fn get_flash(exif: &Exif) -> Option<String> {
+    let flash = get_uint(exif, Tag::Flash)?;
+    let fired = flash & 0x01 != 0;
+    let auto = (flash >> 3) & 0x03 == 3;
+    let red_eye = flash & 0x40 != 0;
+
+    let mut description = match (fired, flash & 0x20 != 0) {
+        (true, _) => "Flash fired".to_string(),
+        (false, true) => return Some("No flash function".to_string()),
+        (false, false) => "No flash".to_string(),
+    };
+    if fired && auto {
+        description.push_str(", auto");
+    }
+    if fired && red_eye {
+        description.push_str(", red-eye reduction");
+    }
+    Some(description)
+}
+
+/// Auto or the light source of manually set white balance
+pub fn get_white_balance(exif: &Exif) -> Option<String> {
+    let light_source = get_uint(exif, Tag::LightSource).and_then(|light_source| {
+        Some(match light_source {
+            1 => "Daylight",
+            2 => "Fluorescent",
+            3 => "Tungsten",
+            4 => "Flash",
+            9 => "Fine weather",
+            10 => "Cloudy",
+            11 => "Shade",
+            17..=19 => "Standard light",
+            _ => return None,
+        })
+    });
+    let white_balance = match (get_uint(exif, Tag::WhiteBalance), light_source) {
+        (Some(0), _) => "Auto WB",
+        (Some(1), Some(light_source)) => light_source,
+        (Some(1), None) => "Manual WB",
+        (None, Some(light_source)) => light_source,
+        _ => return None,
+    };
+    Some(white_balance.to_string())
+}
+
+pub fn get_exposure_program(exif: &Exif) -> Option<String> {
+    let program = match get_uint(exif, Tag::ExposureProgram)? {
+        1 => "Manual",
+        2 => "Program",
+        3 => "Aperture priority",
+        4 => "Shutter priority",
+        5 => "Creative",
+        6 => "Action",
+        7 => "Portrait",
+        8 => "Landscape",
+        _ => return None,
+    };
+    Some(program.to_string())
+}
+
+#[test]
+fn test_format_exposure_bias() {
+    assert_eq!(format_exposure_bias(0.), "0 EV");
+    assert_eq!(format_exposure_bias(1. / 3.), "+⅓ EV");
+    assert_eq!(format_exposure_bias(-0.67), "-⅔ EV");
+    assert_eq!(format_exposure_bias(1.5), "+1½ EV");
+    assert_eq!(format_exposure_bias(-2.), "-2 EV");
+    assert_eq!(format_exposure_bias(-5. / 3.), "-1⅔ EV");
+    assert_eq!(format_exposure_bias(0.8), "+0.8 EV");
+}
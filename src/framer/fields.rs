@@ -0,0 +1,285 @@
+use anyhow::{bail, Result};
+use exif::In;
+use log::debug;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::config::Config;
+
+use super::{
+    metadata::Metadata, overrides::normalize_key, text_values::get_builtin_fields, Resources,
+};
+
+/// Value of the field read from the metadata before it's formatted
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawValue {
+    Text(String),
+    Number(f64),
+}
+
+pub type Extractor = Box<dyn Fn(&Metadata, &Config, &Resources) -> Option<RawValue>>;
+pub type Formatter = Box<dyn Fn(&RawValue, &Config) -> Option<String>>;
+
+/// Value that can be displayed in the frame and used in the templates as `text_values.<name>`
+pub struct Field {
+    pub name: String,
+    /// Template file of the icon displayed in front of the value
+    pub icon: Option<String>,
+    pub extractor: Extractor,
+    pub formatter: Formatter,
+}
+
+impl Field {
+    /// Field whose extractor already returns the formatted text
+    pub fn text(
+        name: &str,
+        icon: Option<&str>,
+        extractor: impl Fn(&Metadata, &Config, &Resources) -> Option<String> + 'static,
+    ) -> Field {
+        Field {
+            name: name.to_string(),
+            icon: icon.map(str::to_string),
+            extractor: Box::new(move |metadata, config, resources| {
+                extractor(metadata, config, resources).map(RawValue::Text)
+            }),
+            formatter: Box::new(|value, _| Some(format_raw_value(value))),
+        }
+    }
+
+    /// Field with the number formatted according to the config
+    pub fn number(
+        name: &str,
+        icon: Option<&str>,
+        extractor: impl Fn(&Metadata, &Config, &Resources) -> Option<f64> + 'static,
+        formatter: impl Fn(f64, &Config) -> Option<String> + 'static,
+    ) -> Field {
+        Field {
+            name: name.to_string(),
+            icon: icon.map(str::to_string),
+            extractor: Box::new(move |metadata, config, resources| {
+                extractor(metadata, config, resources).map(RawValue::Number)
+            }),
+            // Text set manually is formatted as well when it's a number like `2.8`
+            formatter: Box::new(move |value, config| match value {
+                RawValue::Number(number) => formatter(*number, config),
+                RawValue::Text(text) => match text.parse::<f64>() {
+                    Ok(number) => formatter(number, config),
+                    Err(_) => Some(text.clone()),
+                },
+            }),
+        }
+    }
+
+    pub fn get_value(
+        &self,
+        metadata: &Metadata,
+        config: &Config,
+        resources: &Resources,
+    ) -> Option<String> {
+        let value = (self.extractor)(metadata, config, resources)?;
+        (self.formatter)(&value, config).filter(|text| !text.is_empty())
+    }
+}
+
+/// Fields known to the framer, the built-in ones followed by the `[[field]]` entries of the config
+#[derive(Default)]
+pub struct FieldRegistry {
+    fields: Vec<Field>,
+}
+
+impl FieldRegistry {
+    pub fn load(config: &Config) -> FieldRegistry {
+        let mut registry = FieldRegistry::default();
+        get_builtin_fields()
+            .into_iter()
+            .for_each(|field| registry.register(field));
+        config
+            .custom_fields
+            .iter()
+            .for_each(|field| registry.register(field.to_field()));
+        registry
+    }
+
+    /// Adds the field, the one already registered with the same name is replaced
+    pub fn register(&mut self, field: Field) {
+        match self
+            .fields
+            .iter_mut()
+            .find(|registered| registered.name == field.name)
+        {
+            Some(registered) => *registered = field,
+            None => self.fields.push(field),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn has_icon(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|field| field.icon.is_some())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter()
+    }
+
+    /// Leaves out the icons missing in the template, templates made before the icon was added
+    /// display the value without it and without the space for it
+    pub fn remove_missing_icons(&mut self, templates_path: &Path) {
+        for field in &mut self.fields {
+            if let Some(icon) = &field.icon {
+                if !templates_path.join(icon).exists() {
+                    debug!(
+                        "{:?} is missing in {:?}, `{}` is displayed without an icon",
+                        icon, templates_path, field.name
+                    );
+                    field.icon = None;
+                }
+            }
+        }
+    }
+}
+
+/// Names of the templates the icons of the fields are registered next to
+const RESERVED_NAMES: [&str; 1] = ["main"];
+
+/// Fails on the custom fields whose icon would replace a template
+pub fn check_custom_fields(config: &Config) -> Result<()> {
+    for field in &config.custom_fields {
+        if RESERVED_NAMES.contains(&normalize_key(&field.name).as_str()) {
+            bail!("field name `{}` is reserved", field.name);
+        }
+    }
+    Ok(())
+}
+
+/// Field defined in the config file, read from the first of the sources found in the metadata
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CustomField {
+    pub name: String,
+    /// EXIF tag like `ImageDescription`
+    pub exif: Option<String>,
+    /// XMP property like `dc:source`
+    pub xmp: Option<String>,
+    /// IPTC-IIM dataset like `Source`
+    pub iptc: Option<String>,
+    /// Maker note field like `FilmSimulation`
+    pub maker_note: Option<String>,
+    /// Text with the `{value}` placeholder
+    pub format: Option<String>,
+    /// Icon file in the template directory
+    pub icon: Option<String>,
+}
+
+impl CustomField {
+    pub fn to_field(&self) -> Field {
+        let source = self.clone();
+        let format = self.format.clone();
+        Field {
+            name: self.name.clone(),
+            icon: self.icon.clone(),
+            extractor: Box::new(move |metadata, _, _| source.extract(metadata).map(RawValue::Text)),
+            formatter: Box::new(move |value, _| {
+                let value = format_raw_value(value);
+                Some(match &format {
+                    Some(format) => format.replace("{value}", &value),
+                    None => value,
+                })
+            }),
+        }
+    }
+
+    fn extract(&self, metadata: &Metadata) -> Option<String> {
+        let exif = || {
+            let tag = self.exif.as_ref()?;
+            metadata
+                .exif
+                .fields()
+                .find(|field| field.ifd_num == In::PRIMARY && field.tag.to_string() == *tag)
+                .map(|field| {
+                    field
+                        .display_value()
+                        .to_string()
+                        .trim_matches('"')
+                        .to_string()
+                })
+        };
+        let xmp = || metadata.xmp.get_text(self.xmp.as_ref()?);
+        let iptc = || metadata.iptc.get_text(self.iptc.as_ref()?);
+        let maker_note = || {
+            let name = self.maker_note.as_ref()?;
+            metadata.maker_note_fields.get(name.as_str()).cloned()
+        };
+        xmp()
+            .or_else(iptc)
+            .or_else(exif)
+            .or_else(maker_note)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Text as it is, numbers without the trailing zeros
+fn format_raw_value(value: &RawValue) -> String {
+    match value {
+        RawValue::Text(text) => text.clone(),
+        RawValue::Number(number) => format!("{}", number),
+    }
+}
+
+#[test]
+fn test_register_field() {
+    let mut registry = FieldRegistry::load(&Config::default());
+    assert!(registry.has_icon("camera"));
+    assert!(!registry.has_icon("title"));
+
+    registry.register(Field::text("camera", None, |_, _, _| {
+        Some("Pinhole".to_string())
+    }));
+    assert!(!registry.has_icon("camera"));
+    assert_eq!(
+        registry
+            .iter()
+            .filter(|field| field.name == "camera")
+            .count(),
+        1
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("iso-icon.svg"), "<svg/>").unwrap();
+    registry.remove_missing_icons(dir.path());
+    assert!(registry.has_icon("iso"));
+    assert!(!registry.has_icon("lens"));
+
+    let mut config = Config::default();
+    config.custom_fields.push(CustomField {
+        name: "Main".to_string(),
+        exif: None,
+        xmp: None,
+        iptc: None,
+        maker_note: None,
+        format: None,
+        icon: None,
+    });
+    assert!(check_custom_fields(&config).is_err());
+
+    let field = CustomField {
+        name: "source".to_string(),
+        exif: None,
+        xmp: None,
+        iptc: None,
+        maker_note: None,
+        format: Some("Scan: {value}".to_string()),
+        icon: None,
+    }
+    .to_field();
+    assert_eq!(
+        (field.formatter)(
+            &RawValue::Text("Epson V600".to_string()),
+            &Config::default()
+        ),
+        Some("Scan: Epson V600".to_string())
+    );
+}
@@ -0,0 +1,175 @@
+use clap::ValueEnum;
+use exif::{Exif, Tag};
+use log::debug;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+use super::{
+    camera::{find_camera_entry, CameraEntry},
+    formatter::{format_aperture, ApertureFormat},
+    profile::{get_profile, get_profile_crop_factor},
+    text_values::{get_focal_length, get_number},
+};
+
+/// Diagonal of the 36x24mm full frame
+const FULL_FRAME_DIAGONAL: f64 = 43.267;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FocalLengthDisplay {
+    /// Focal length of the lens: 23mm
+    #[default]
+    Actual,
+    /// 35mm-equivalent focal length: 35mm
+    Equivalent,
+    /// Both of them: 23mm (35mm)
+    Both,
+}
+
+/// Crop factor used for the camera that doesn't store enough information to compute it
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CameraCropFactor {
+    pub make: String,
+    pub model: String,
+    pub crop_factor: f64,
+}
+
+impl CameraEntry for CameraCropFactor {
+    fn camera(&self) -> (&str, &str) {
+        (&self.make, &self.model)
+    }
+}
+
+/// Ratio between the full frame diagonal and the sensor diagonal
+///
+/// Resolved from `FocalLengthIn35mmFilm`, then from the focal plane resolution and the image size
+/// and finally from the `camera-crop-factor` table in the config.
+/// The EXIF of the scanner is not used with a film profile, the crop factor is the one of the profile.
+pub fn get_crop_factor(exif: &Exif, config: &Config) -> Option<f64> {
+    let crop_factor = match get_profile(config) {
+        Some(profile) => get_profile_crop_factor(profile, config),
+        None => get_crop_factor_from_35mm_film(exif)
+            .or_else(|| get_crop_factor_from_focal_plane(exif))
+            .or_else(|| get_configured_crop_factor(exif, config)),
+    };
+    debug!("Crop factor: {:?}", crop_factor);
+    crop_factor
+}
+
+fn get_crop_factor_from_35mm_film(exif: &Exif) -> Option<f64> {
+    let focal_length = get_number(exif, Tag::FocalLength)?;
+    let equivalent = get_number(exif, Tag::FocalLengthIn35mmFilm)?;
+    Some(equivalent / focal_length)
+        .filter(|crop_factor| crop_factor.is_finite() && *crop_factor > 0.)
+}
+
+fn get_crop_factor_from_focal_plane(exif: &Exif) -> Option<f64> {
+    let unit = match get_number(exif, Tag::FocalPlaneResolutionUnit).unwrap_or(2.) as u32 {
+        2 => 25.4,
+        3 => 10.,
+        4 => 1.,
+        5 => 0.001,
+        _ => return None,
+    };
+    let x_resolution = get_number(exif, Tag::FocalPlaneXResolution)?;
+    let y_resolution = get_number(exif, Tag::FocalPlaneYResolution).unwrap_or(x_resolution);
+    let width =
+        get_number(exif, Tag::PixelXDimension).or_else(|| get_number(exif, Tag::ImageWidth))?;
+    let height =
+        get_number(exif, Tag::PixelYDimension).or_else(|| get_number(exif, Tag::ImageLength))?;
+
+    let sensor_width = width / x_resolution * unit;
+    let sensor_height = height / y_resolution * unit;
+    let sensor_diagonal = sensor_width.hypot(sensor_height);
+    Some(FULL_FRAME_DIAGONAL / sensor_diagonal)
+        .filter(|crop_factor| crop_factor.is_finite() && *crop_factor > 0.)
+}
+
+fn get_configured_crop_factor(exif: &Exif, config: &Config) -> Option<f64> {
+    find_camera_entry(exif, &config.camera_crop_factors).map(|camera| camera.crop_factor)
+}
+
+/// Actual and equivalent focal length of the film profile's lens or the one from EXIF
+pub fn get_focal_lengths(
+    exif: &Exif,
+    config: &Config,
+    crop_factor: Option<f64>,
+) -> (Option<String>, Option<String>) {
+    let Some(profile) = get_profile(config) else {
+        return (
+            get_focal_length(exif),
+            get_equivalent_focal_length(exif, crop_factor),
+        );
+    };
+    let format = |focal_length: f64| format!("{:.0}mm", focal_length);
+    let equivalent = profile
+        .focal_length
+        .zip(crop_factor)
+        .map(|(focal_length, crop_factor)| format(focal_length * crop_factor));
+    (profile.focal_length.map(format), equivalent)
+}
+
+fn get_equivalent_focal_length(exif: &Exif, crop_factor: Option<f64>) -> Option<String> {
+    let equivalent = get_number(exif, Tag::FocalLengthIn35mmFilm)
+        .filter(|equivalent| *equivalent > 0.)
+        .or_else(|| Some(get_number(exif, Tag::FocalLength)? * crop_factor?))?;
+    Some(format!("{:.0}mm", equivalent))
+}
+
+pub fn get_equivalent_aperture(
+    exif: &Exif,
+    crop_factor: Option<f64>,
+    format: ApertureFormat,
+) -> Option<String> {
+    let aperture = get_number(exif, Tag::FNumber)? * crop_factor?;
+    format_aperture(aperture, format)
+}
+
+/// Combines the actual and the equivalent focal length according to the display mode
+pub fn format_focal_length(
+    actual: Option<String>,
+    equivalent: Option<String>,
+    display: FocalLengthDisplay,
+) -> Option<String> {
+    match (display, actual, equivalent) {
+        (FocalLengthDisplay::Actual, actual, _) => actual,
+        (FocalLengthDisplay::Equivalent, _, equivalent) => equivalent,
+        (FocalLengthDisplay::Both, Some(actual), Some(equivalent)) if actual != equivalent => {
+            Some(format!("{} ({})", actual, equivalent))
+        }
+        (FocalLengthDisplay::Both, actual, equivalent) => actual.or(equivalent),
+    }
+}
+
+#[test]
+fn test_format_focal_length() {
+    let actual = || Some("23mm".to_string());
+    let equivalent = || Some("35mm".to_string());
+
+    assert_eq!(
+        format_focal_length(actual(), equivalent(), FocalLengthDisplay::Actual),
+        actual()
+    );
+    assert_eq!(
+        format_focal_length(actual(), equivalent(), FocalLengthDisplay::Equivalent),
+        equivalent()
+    );
+    assert_eq!(
+        format_focal_length(actual(), equivalent(), FocalLengthDisplay::Both),
+        Some("23mm (35mm)".to_string())
+    );
+    assert_eq!(
+        format_focal_length(actual(), None, FocalLengthDisplay::Both),
+        actual()
+    );
+    assert_eq!(
+        format_focal_length(
+            Some("50mm".to_string()),
+            Some("50mm".to_string()),
+            FocalLengthDisplay::Both
+        ),
+        Some("50mm".to_string())
+    );
+}
@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use dirs::config_dir;
+use fontdb::{Database, Family, Query, Weight};
+use log::{debug, warn};
+use rustybuzz::UnicodeBuffer;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+/// Font of the values declared by the template in the `[font]` table of `manifest.toml`
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FontDescription {
+    pub family: String,
+    /// Size in pixels of the frame
+    pub size: f64,
+    pub weight: u16,
+}
+
+impl Default for FontDescription {
+    fn default() -> Self {
+        FontDescription {
+            family: "IntelOne Mono".to_string(),
+            size: 18.,
+            weight: 500,
+        }
+    }
+}
+
+/// Font file used to measure the width of the displayed text
+pub struct Font {
+    /// Parsed once, the font data is loaded once per run and kept until it ends
+    face: rustybuzz::Face<'static>,
+    pub size: f64,
+}
+
+impl Font {
+    /// Looks up the font in the user font directory, the template directory and the system fonts
+    pub fn load(
+        description: &FontDescription,
+        config: &Config,
+        template_path: &Path,
+    ) -> Option<Font> {
+        let mut database = Database::new();
+        database.load_fonts_dir(get_fonts_path(config));
+        database.load_fonts_dir(template_path);
+        database.load_system_fonts();
+
+        let families = [Family::Name(&description.family)];
+        let query = Query {
+            families: &families,
+            weight: Weight(description.weight),
+            ..Query::default()
+        };
+        let Some(id) = database.query(&query) else {
+            warn!(
+                "font `{}` was not found, the width of the text is estimated",
+                description.family
+            );
+            return None;
+        };
+        debug!("Measuring text with {:?}", database.face(id)?.source);
+        let (data, index) = database.with_face_data(id, |data, index| (data.to_vec(), index))?;
+        let data: &'static [u8] = Box::leak(data.into_boxed_slice());
+        // Fonts that can't be shaped are handled as not found
+        let face = rustybuzz::Face::from_slice(data, index)?;
+        Some(Font {
+            face,
+            size: description.size,
+        })
+    }
+
+    /// Width of the shaped text in pixels, the sum of glyph advances including kerning
+    pub fn measure(&self, text: &str) -> f64 {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        let glyphs = rustybuzz::shape(&self.face, &[], buffer);
+        let advance: i32 = glyphs
+            .glyph_positions()
+            .iter()
+            .map(|position| position.x_advance)
+            .sum();
+        advance as f64 * self.size / self.face.units_per_em() as f64
+    }
+}
+
+/// Directory with the user fonts, `fonts` in the configuration directory by default
+fn get_fonts_path(config: &Config) -> PathBuf {
+    match &config.fonts {
+        Some(fonts) => fonts.clone(),
+        None => Path::new(&config_dir().unwrap()).join("metaframer/fonts"),
+    }
+}
+
+#[test]
+fn test_measure() {
+    let description = FontDescription {
+        family: "Tuffy".to_string(),
+        size: 20.,
+        weight: 400,
+    };
+    let config = Config {
+        fonts: Some(PathBuf::from("tests/assets/fonts")),
+        ..Config::default()
+    };
+    let font = Font::load(&description, &config, Path::new("templates")).unwrap();
+    assert!(font.measure("iii") < font.measure("MMM"));
+    assert!(font.measure("AV") < font.measure("A") + font.measure("V"));
+    assert_eq!(font.measure(""), 0.);
+}
@@ -0,0 +1,140 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Longest exposure the cameras usually time themselves, longer ones are taken in bulb mode
+const LONGEST_TIMED_EXPOSURE: f64 = 30.;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShutterSpeedFormat {
+    /// Fractions and seconds with the unit: 1/250s, 2.5s
+    #[default]
+    Fraction,
+    /// Notation of the camera displays: 1/250, 2.5"
+    Compact,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApertureFormat {
+    /// f/2.8
+    #[default]
+    Slash,
+    /// ƒ2.8
+    Hook,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IsoFormat {
+    /// 100
+    #[default]
+    Plain,
+    /// ISO 100
+    Prefix,
+}
+
+/// Formats the exposure time in seconds: 1/8000s, 1/3s, 0.4s, 2.5s, 30s, Bulb 4m
+pub fn format_shutter_speed(seconds: f64, format: ShutterSpeedFormat) -> Option<String> {
+    if !seconds.is_finite() || seconds <= 0. {
+        return None;
+    }
+    let unit = match format {
+        ShutterSpeedFormat::Fraction => "s",
+        ShutterSpeedFormat::Compact => "",
+    };
+
+    if seconds > LONGEST_TIMED_EXPOSURE {
+        let seconds = seconds.round() as u64;
+        let duration = match (seconds / 60, seconds % 60) {
+            (0, seconds) => format!("{}s", seconds),
+            (minutes, 0) => format!("{}m", minutes),
+            (minutes, seconds) => format!("{}m{}s", minutes, seconds),
+        };
+        return Some(format!("Bulb {}", duration));
+    }
+
+    if seconds < 1. {
+        let denominator = (1. / seconds).round();
+        // Speeds like 0.4s can't be written as a fraction with the numerator 1
+        if ((1. / denominator) - seconds).abs() / seconds < 0.05 {
+            return Some(format!("1/{}{}", denominator, unit));
+        }
+    }
+
+    let seconds = format_number(seconds, 1);
+    Some(match format {
+        ShutterSpeedFormat::Fraction => format!("{}s", seconds),
+        ShutterSpeedFormat::Compact => format!("{}\"", seconds),
+    })
+}
+
+pub fn format_aperture(f_number: f64, format: ApertureFormat) -> Option<String> {
+    if !f_number.is_finite() || f_number <= 0. {
+        return None;
+    }
+    // f/0.95 lenses need the second decimal place
+    let f_number = format_number(f_number, if f_number < 1. { 2 } else { 1 });
+    Some(match format {
+        ApertureFormat::Slash => format!("f/{}", f_number),
+        ApertureFormat::Hook => format!("ƒ{}", f_number),
+    })
+}
+
+pub fn format_iso(iso: u32, format: IsoFormat) -> String {
+    match format {
+        IsoFormat::Plain => iso.to_string(),
+        IsoFormat::Prefix => format!("ISO {}", iso),
+    }
+}
+
+/// Number with at most the given decimal places without the trailing zeros
+fn format_number(number: f64, decimal_places: usize) -> String {
+    let formatted = format!("{:.*}", decimal_places, number);
+    match formatted.contains('.') {
+        true => formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string(),
+        false => formatted,
+    }
+}
+
+#[test]
+fn test_format_shutter_speed() {
+    let fraction = |seconds| format_shutter_speed(seconds, ShutterSpeedFormat::Fraction).unwrap();
+    assert_eq!(fraction(1. / 8000.), "1/8000s");
+    assert_eq!(fraction(1. / 3.), "1/3s");
+    assert_eq!(fraction(0.5), "1/2s");
+    assert_eq!(fraction(0.4), "0.4s");
+    assert_eq!(fraction(1.), "1s");
+    assert_eq!(fraction(2.5), "2.5s");
+    assert_eq!(fraction(30.), "30s");
+    assert_eq!(fraction(45.), "Bulb 45s");
+    assert_eq!(fraction(240.), "Bulb 4m");
+    assert_eq!(fraction(90.), "Bulb 1m30s");
+
+    let compact = |seconds| format_shutter_speed(seconds, ShutterSpeedFormat::Compact).unwrap();
+    assert_eq!(compact(1. / 250.), "1/250");
+    assert_eq!(compact(2.5), "2.5\"");
+
+    assert_eq!(format_shutter_speed(0., ShutterSpeedFormat::Fraction), None);
+}
+
+#[test]
+fn test_format_aperture() {
+    assert_eq!(
+        format_aperture(2.8, ApertureFormat::Slash),
+        Some("f/2.8".to_string())
+    );
+    assert_eq!(
+        format_aperture(8., ApertureFormat::Hook),
+        Some("ƒ8".to_string())
+    );
+    assert_eq!(
+        format_aperture(0.95, ApertureFormat::Slash),
+        Some("f/0.95".to_string())
+    );
+    assert_eq!(format_iso(100, IsoFormat::Prefix), "ISO 100");
+    assert_eq!(format_iso(100, IsoFormat::Plain), "100");
+}
@@ -0,0 +1,227 @@
+use anyhow::Context;
+use dirs::config_dir;
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+use super::gps::Coordinates;
+
+const EARTH_RADIUS_KM: f64 = 6371.;
+
+#[derive(Debug, PartialEq)]
+pub struct City {
+    pub name: String,
+    pub country_code: String,
+    pub coordinates: Coordinates,
+}
+
+/// Named area from the user's places file, matched before any city
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Place {
+    pub name: String,
+    #[serde(flatten)]
+    pub area: Area,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Area {
+    /// Circle around the point with radius in meters
+    Radius {
+        latitude: f64,
+        longitude: f64,
+        radius: f64,
+    },
+    /// List of `[latitude, longitude]` points
+    Polygon { polygon: Vec<[f64; 2]> },
+}
+
+#[derive(Deserialize)]
+struct PlacesFile {
+    #[serde(default)]
+    places: Vec<Place>,
+}
+
+/// Offline reverse geocoder translating coordinates into "City, Country"
+pub struct Geocoder {
+    cities: Vec<City>,
+    countries: HashMap<String, String>,
+    places: Vec<Place>,
+    max_distance: f64,
+}
+
+impl Geocoder {
+    pub fn load(config: &Config) -> Result<Geocoder, anyhow::Error> {
+        let cities = match &config.geonames {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .with_context(|| format!("could not read GeoNames dataset `{:?}`", path))?;
+                parse_geonames(&content)
+            }
+            None => parse_geonames(include_str!("../../data/cities.txt")),
+        };
+        debug!("Loaded {} cities for reverse geocoding", cities.len());
+
+        let places_path = config.places.clone().unwrap_or_else(get_places_path);
+        let places = if places_path.exists() {
+            let content = fs::read_to_string(&places_path)
+                .with_context(|| format!("could not read places file `{:?}`", places_path))?;
+            parse_places(&content)
+                .with_context(|| format!("invalid places file `{:?}`", places_path))?
+        } else {
+            vec![]
+        };
+
+        Ok(Geocoder {
+            cities,
+            countries: parse_countries(include_str!("../../data/countries.txt")),
+            places,
+            max_distance: config.geocoding_max_distance,
+        })
+    }
+
+    pub fn reverse(&self, coordinates: Coordinates) -> Option<String> {
+        if let Some(place) = self
+            .places
+            .iter()
+            .find(|place| place.area.contains(coordinates))
+        {
+            return Some(place.name.clone());
+        }
+
+        let (city, distance) = self
+            .cities
+            .iter()
+            .map(|city| (city, get_distance(city.coordinates, coordinates)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        if distance > self.max_distance {
+            debug!("Nearest city {} is {:.1}km away", city.name, distance);
+            return None;
+        }
+
+        let country = self
+            .countries
+            .get(&city.country_code)
+            .unwrap_or(&city.country_code);
+        Some(format!("{}, {}", city.name, country))
+    }
+}
+
+impl Area {
+    pub fn contains(&self, coordinates: Coordinates) -> bool {
+        match self {
+            Area::Radius {
+                latitude,
+                longitude,
+                radius,
+            } => {
+                let center = Coordinates {
+                    latitude: *latitude,
+                    longitude: *longitude,
+                };
+                get_distance(center, coordinates) * 1000. <= *radius
+            }
+            Area::Polygon { polygon } => {
+                // Ray casting, the polygons are small enough to treat the coordinates as planar
+                let (y, x) = (coordinates.latitude, coordinates.longitude);
+                let mut is_inside = false;
+                for (index, [y1, x1]) in polygon.iter().enumerate() {
+                    let [y2, x2] = polygon[(index + 1) % polygon.len()];
+                    if (y1 > &y) != (y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1 {
+                        is_inside = !is_inside;
+                    }
+                }
+                is_inside
+            }
+        }
+    }
+}
+
+pub fn get_places_path() -> PathBuf {
+    let config_dir = config_dir().unwrap();
+    Path::new(&config_dir).join("metaframer/places.toml")
+}
+
+/// Parses GeoNames `cities*.txt` dump, only name, coordinates and country code columns are used
+pub fn parse_geonames(content: &str) -> Vec<City> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            Some(City {
+                name: columns.get(1)?.to_string(),
+                country_code: columns.get(8)?.to_string(),
+                coordinates: Coordinates {
+                    latitude: columns.get(4)?.parse().ok()?,
+                    longitude: columns.get(5)?.parse().ok()?,
+                },
+            })
+        })
+        .collect()
+}
+
+fn parse_countries(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(code, name)| (code.to_string(), name.to_string()))
+        .collect()
+}
+
+pub fn parse_places(content: &str) -> Result<Vec<Place>, anyhow::Error> {
+    let places_file: PlacesFile = toml::from_str(content)?;
+    Ok(places_file.places)
+}
+
+/// Great-circle distance in kilometers
+pub fn get_distance(a: Coordinates, b: Coordinates) -> f64 {
+    let (lat_a, lat_b) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let delta_lat = lat_b - lat_a;
+    let delta_lon = (b.longitude - a.longitude).to_radians();
+    let h =
+        (delta_lat / 2.).sin().powi(2) + lat_a.cos() * lat_b.cos() * (delta_lon / 2.).sin().powi(2);
+    2. * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+#[test]
+fn test_reverse_geocoding() {
+    let geocoder = Geocoder {
+        cities: parse_geonames(include_str!("../../data/cities.txt")),
+        countries: parse_countries(include_str!("../../data/countries.txt")),
+        places: parse_places(
+            r#"
+            [[places]]
+            name = "Home studio"
+            latitude = 48.1500
+            longitude = 17.1100
+            radius = 200
+
+            [[places]]
+            name = "Danube island"
+            polygon = [[48.25, 16.38], [48.25, 16.42], [48.20, 16.42], [48.20, 16.38]]
+            "#,
+        )
+        .unwrap(),
+        max_distance: 50.,
+    };
+
+    let reverse = |latitude, longitude| {
+        geocoder.reverse(Coordinates {
+            latitude,
+            longitude,
+        })
+    };
+    assert_eq!(reverse(48.1505, 17.1102), Some("Home studio".to_string()));
+    assert_eq!(reverse(48.2200, 16.4000), Some("Danube island".to_string()));
+    assert_eq!(
+        reverse(48.1600, 17.1500),
+        Some("Bratislava, Slovakia".to_string())
+    );
+    assert_eq!(reverse(35.6000, 139.7000), Some("Tokyo, Japan".to_string()));
+    // Middle of the Atlantic ocean
+    assert_eq!(reverse(30.0000, -40.0000), None);
+}
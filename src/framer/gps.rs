@@ -0,0 +1,162 @@
+use clap::ValueEnum;
+use exif::{Exif, In, Tag, Value};
+use serde::Deserialize;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum GpsFormat {
+    /// Degrees, minutes and seconds: 48°8'51.2"N
+    #[default]
+    Dms,
+    /// Signed decimal degrees: 48.14756
+    Decimal,
+    /// Decimal degrees with the cardinal direction: 48.1476° N
+    Compass,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position {
+    pub coordinates: Coordinates,
+    /// Altitude in meters
+    pub altitude: Option<f64>,
+}
+
+pub fn get_position(exif: &Exif) -> Option<Position> {
+    Some(Position {
+        coordinates: get_coordinates(exif)?,
+        altitude: get_altitude(exif),
+    })
+}
+
+pub fn get_coordinates(exif: &Exif) -> Option<Coordinates> {
+    Some(Coordinates {
+        latitude: get_degrees(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?,
+        longitude: get_degrees(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?,
+    })
+}
+
+fn get_degrees(exif: &Exif, tag: Tag, ref_tag: Tag, negative_ref: &str) -> Option<f64> {
+    let degrees = match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(dms) if dms.len() == 3 => {
+            dms[0].to_f64() + dms[1].to_f64() / 60. + dms[2].to_f64() / 3600.
+        }
+        _ => return None,
+    };
+    if !degrees.is_finite() {
+        return None;
+    }
+    let is_negative = exif
+        .get_field(ref_tag, In::PRIMARY)
+        .map(|field| field.display_value().to_string().contains(negative_ref))
+        .unwrap_or(false);
+    Some(if is_negative { -degrees } else { degrees })
+}
+
+/// Altitude in meters, negative when the reference is below the sea level
+pub fn get_altitude(exif: &Exif) -> Option<f64> {
+    let altitude = match &exif.get_field(Tag::GPSAltitude, In::PRIMARY)?.value {
+        Value::Rational(altitude) => altitude.first()?.to_f64(),
+        _ => return None,
+    };
+    let is_below_sea_level = exif
+        .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        == Some(1);
+    Some(if is_below_sea_level {
+        -altitude
+    } else {
+        altitude
+    })
+    .filter(|altitude| altitude.is_finite())
+}
+
+/// Direction in which the image was taken in degrees
+pub fn get_image_direction(exif: &Exif) -> Option<f64> {
+    match &exif.get_field(Tag::GPSImgDirection, In::PRIMARY)?.value {
+        Value::Rational(direction) => Some(direction.first()?.to_f64()).filter(|d| d.is_finite()),
+        _ => None,
+    }
+}
+
+pub fn format_latitude(latitude: f64, format: GpsFormat) -> String {
+    format_coordinate(latitude, ('N', 'S'), format)
+}
+
+pub fn format_longitude(longitude: f64, format: GpsFormat) -> String {
+    format_coordinate(longitude, ('E', 'W'), format)
+}
+
+fn format_coordinate(value: f64, (positive, negative): (char, char), format: GpsFormat) -> String {
+    let hemisphere = if value < 0. { negative } else { positive };
+    let absolute = value.abs();
+    match format {
+        GpsFormat::Dms => {
+            // Rounded to tenths of a second first so 59.96" carries into the minutes
+            let tenths = (absolute * 36000.).round() as u64;
+            let degrees = tenths / 36000;
+            let minutes = tenths % 36000 / 600;
+            let seconds = (tenths % 600) as f64 / 10.;
+            format!("{}°{}'{:.1}\"{}", degrees, minutes, seconds, hemisphere)
+        }
+        GpsFormat::Decimal => format!("{:.5}", value),
+        GpsFormat::Compass => format!("{:.4}° {}", absolute, hemisphere),
+    }
+}
+
+pub fn format_altitude(altitude: f64) -> String {
+    format!("{:.0}m", altitude)
+}
+
+pub fn format_direction(direction: f64, format: GpsFormat) -> String {
+    // Rounded to the displayed precision first so 359.6° is displayed as 0°
+    let normalize = |precision: f64| ((direction * precision).round() / precision).rem_euclid(360.);
+    match format {
+        GpsFormat::Dms => format!("{:.0}°", normalize(1.)),
+        GpsFormat::Decimal => format!("{:.1}°", normalize(10.)),
+        GpsFormat::Compass => {
+            let direction = normalize(1.);
+            let points = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+            let index = ((direction + 22.5) / 45.) as usize % points.len();
+            format!("{} {:.0}°", points[index], direction)
+        }
+    }
+}
+
+#[test]
+fn test_format_coordinates() {
+    let latitude = 48.147556;
+    let longitude = -17.10772;
+
+    assert_eq!(format_latitude(latitude, GpsFormat::Dms), "48°8'51.2\"N");
+    assert_eq!(format_longitude(longitude, GpsFormat::Dms), "17°6'27.8\"W");
+    // Seconds rounded up to a whole minute or degree are carried
+    let minute_carry = 48. + 8. / 60. + 59.96 / 3600.;
+    assert_eq!(format_latitude(minute_carry, GpsFormat::Dms), "48°9'0.0\"N");
+    let degree_carry = 48. + 59. / 60. + 59.99 / 3600.;
+    assert_eq!(format_latitude(degree_carry, GpsFormat::Dms), "49°0'0.0\"N");
+    assert_eq!(format_latitude(latitude, GpsFormat::Decimal), "48.14756");
+    assert_eq!(format_longitude(longitude, GpsFormat::Decimal), "-17.10772");
+    assert_eq!(format_latitude(latitude, GpsFormat::Compass), "48.1476° N");
+    assert_eq!(
+        format_longitude(longitude, GpsFormat::Compass),
+        "17.1077° W"
+    );
+}
+
+#[test]
+fn test_format_direction() {
+    assert_eq!(format_direction(44.6, GpsFormat::Dms), "45°");
+    assert_eq!(format_direction(44.6, GpsFormat::Decimal), "44.6°");
+    assert_eq!(format_direction(44.6, GpsFormat::Compass), "NE 45°");
+    assert_eq!(format_direction(350., GpsFormat::Compass), "N 350°");
+    assert_eq!(format_direction(359.6, GpsFormat::Compass), "N 0°");
+    assert_eq!(format_direction(360., GpsFormat::Compass), "N 0°");
+    assert_eq!(format_direction(405., GpsFormat::Dms), "45°");
+    assert_eq!(format_direction(359.96, GpsFormat::Decimal), "0.0°");
+}
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, TimeDelta, Utc};
+use log::debug;
+use std::fs;
+use std::path::PathBuf;
+
+use super::gps::{Coordinates, Position};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrackPoint {
+    pub time: DateTime<Utc>,
+    pub position: Position,
+}
+
+/// Points of all loaded GPX tracks ordered by time
+pub struct Track {
+    points: Vec<TrackPoint>,
+}
+
+impl Track {
+    pub fn load(paths: &[PathBuf]) -> Result<Track, anyhow::Error> {
+        let mut points = vec![];
+        for path in paths {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("could not read GPX track `{:?}`", path))?;
+            let track_points =
+                parse_gpx(&content).with_context(|| format!("invalid GPX track `{:?}`", path))?;
+            debug!("Loaded {} points from {:?}", track_points.len(), path);
+            points.extend(track_points);
+        }
+        Ok(Track::new(points))
+    }
+
+    pub fn new(mut points: Vec<TrackPoint>) -> Track {
+        points.sort_by_key(|point| point.time);
+        Track { points }
+    }
+
+    /// Interpolates the position at the given time
+    ///
+    /// Points further apart than `max_gap` are not interpolated, the nearest one is used
+    /// only if it is closer than `max_gap` to the time.
+    pub fn get_position(&self, time: DateTime<Utc>, max_gap: TimeDelta) -> Option<Position> {
+        let index = self.points.partition_point(|point| point.time < time);
+        let next = self.points.get(index);
+        let previous = index
+            .checked_sub(1)
+            .and_then(|index| self.points.get(index));
+
+        match (previous, next) {
+            (_, Some(next)) if next.time == time => Some(next.position),
+            (Some(previous), Some(next)) if next.time - previous.time <= max_gap => {
+                let ratio = (time - previous.time).num_milliseconds() as f64
+                    / (next.time - previous.time).num_milliseconds() as f64;
+                Some(interpolate(&previous.position, &next.position, ratio))
+            }
+            _ => [previous, next]
+                .into_iter()
+                .flatten()
+                .map(|point| (point, (point.time - time).abs()))
+                .filter(|(_, distance)| *distance <= max_gap)
+                .min_by_key(|(_, distance)| *distance)
+                .map(|(point, _)| point.position),
+        }
+    }
+}
+
+fn interpolate(from: &Position, to: &Position, ratio: f64) -> Position {
+    let lerp = |a: f64, b: f64| a + (b - a) * ratio;
+    Position {
+        coordinates: Coordinates {
+            latitude: lerp(from.coordinates.latitude, to.coordinates.latitude),
+            longitude: lerp(from.coordinates.longitude, to.coordinates.longitude),
+        },
+        altitude: match (from.altitude, to.altitude) {
+            (Some(from), Some(to)) => Some(lerp(from, to)),
+            (from, to) => from.or(to),
+        },
+    }
+}
+
+/// Reads all timestamped track, route and way points
+pub fn parse_gpx(content: &str) -> Result<Vec<TrackPoint>, anyhow::Error> {
+    let document = roxmltree::Document::parse(content)?;
+    let points = document
+        .descendants()
+        .filter(|node| ["trkpt", "rtept", "wpt"].contains(&node.tag_name().name()))
+        .filter_map(|node| {
+            let child_text = |name: &str| {
+                node.children()
+                    .find(|child| child.tag_name().name() == name)
+                    .and_then(|child| child.text())
+                    .map(|text| text.trim())
+            };
+            let time = DateTime::parse_from_rfc3339(child_text("time")?).ok()?;
+            Some(TrackPoint {
+                time: time.with_timezone(&Utc),
+                position: Position {
+                    coordinates: Coordinates {
+                        latitude: node.attribute("lat")?.parse().ok()?,
+                        longitude: node.attribute("lon")?.parse().ok()?,
+                    },
+                    altitude: child_text("ele").and_then(|ele| ele.parse().ok()),
+                },
+            })
+        })
+        .collect::<Vec<TrackPoint>>();
+
+    if points.is_empty() {
+        return Err(anyhow!("track doesn't contain any points with time"));
+    }
+    Ok(points)
+}
+
+#[test]
+fn test_get_position() {
+    let track = Track::new(
+        parse_gpx(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+              <trk><trkseg>
+                <trkpt lat="48.10" lon="17.10"><ele>100</ele><time>2024-07-14T16:00:00Z</time></trkpt>
+                <trkpt lat="48.20" lon="17.30"><ele>200</ele><time>2024-07-14T16:02:00Z</time></trkpt>
+                <trkpt lat="48.50" lon="17.50"><ele>300</ele><time>2024-07-14T17:00:00Z</time></trkpt>
+              </trkseg></trk>
+            </gpx>"#,
+        )
+        .unwrap(),
+    );
+    let max_gap = TimeDelta::minutes(5);
+    let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().to_utc();
+
+    let position = track
+        .get_position(at("2024-07-14T16:01:00Z"), max_gap)
+        .unwrap();
+    assert!((position.coordinates.latitude - 48.15).abs() < 1e-9);
+    assert!((position.coordinates.longitude - 17.20).abs() < 1e-9);
+    assert_eq!(position.altitude, Some(150.));
+
+    // Gap between the points is too big, but the previous point is close enough
+    let position = track
+        .get_position(at("2024-07-14T16:04:00Z"), max_gap)
+        .unwrap();
+    assert_eq!(position.coordinates.latitude, 48.20);
+
+    assert_eq!(
+        track.get_position(at("2024-07-14T16:30:00Z"), max_gap),
+        None
+    );
+    assert_eq!(
+        track.get_position(at("2024-07-14T18:00:00Z"), max_gap),
+        None
+    );
+}
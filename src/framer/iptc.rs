@@ -0,0 +1,200 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Photoshop image resource holding the IPTC-NAA record
+const IPTC_RESOURCE_ID: u16 = 0x0404;
+
+/// Application record (2) datasets exposed to the templates, keywords are repeatable
+const DATASETS: [(u8, &str); 7] = [
+    (25, "Keywords"),
+    (80, "By-line"),
+    (90, "City"),
+    (101, "Country"),
+    (105, "Headline"),
+    (116, "CopyrightNotice"),
+    (120, "Caption-Abstract"),
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum IptcValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+/// IPTC-IIM datasets read from the APP13 segment of JPEG files
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Iptc {
+    datasets: BTreeMap<String, IptcValue>,
+}
+
+impl Iptc {
+    pub fn read(bytes: &[u8]) -> Iptc {
+        find_iptc_record(bytes).map(parse_iim).unwrap_or_default()
+    }
+
+    /// Text of the dataset, keywords are joined with a comma
+    pub fn get_text(&self, name: &str) -> Option<String> {
+        match self.datasets.get(name)? {
+            IptcValue::Text(text) => Some(text.clone()),
+            IptcValue::List(items) => Some(items.join(", ")),
+        }
+    }
+}
+
+/// Walks JPEG segments up to the image data looking for the IPTC resource in APP13
+fn find_iptc_record(bytes: &[u8]) -> Option<&[u8]> {
+    if bytes.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    let mut offset = 2;
+    loop {
+        let marker = *bytes.get(offset + 1)?;
+        if bytes[offset] != 0xFF || marker == 0xD9 || marker == 0xDA {
+            return None;
+        }
+        let length = usize::from(u16::from_be_bytes([
+            *bytes.get(offset + 2)?,
+            *bytes.get(offset + 3)?,
+        ]));
+        let segment = bytes.get(offset + 4..offset + 2 + length)?;
+        if marker == 0xED {
+            if let Some(record) = segment
+                .strip_prefix(b"Photoshop 3.0\0")
+                .and_then(find_photoshop_resource)
+            {
+                return Some(record);
+            }
+        }
+        offset += 2 + length;
+    }
+}
+
+fn find_photoshop_resource(resources: &[u8]) -> Option<&[u8]> {
+    let mut offset = 0;
+    while resources.get(offset..offset + 4)? == b"8BIM" {
+        let id = u16::from_be_bytes([*resources.get(offset + 4)?, *resources.get(offset + 5)?]);
+        // Pascal string name padded to even length including the length byte
+        let name_length = usize::from(*resources.get(offset + 6)?);
+        let size_offset = offset + 6 + (name_length + 1).next_multiple_of(2);
+        let size = u32::from_be_bytes(
+            resources
+                .get(size_offset..size_offset + 4)?
+                .try_into()
+                .ok()?,
+        );
+        let data_offset = size_offset + 4;
+        let data = resources.get(data_offset..data_offset + size as usize)?;
+        if id == IPTC_RESOURCE_ID {
+            return Some(data);
+        }
+        offset = data_offset + (size as usize).next_multiple_of(2);
+    }
+    None
+}
+
+fn parse_iim(record: &[u8]) -> Iptc {
+    let mut values: Vec<(u8, u8, &[u8])> = vec![];
+    let mut offset = 0;
+    while record.get(offset) == Some(&0x1C) {
+        let Some(header) = record.get(offset + 1..offset + 5) else {
+            break;
+        };
+        let (record_number, dataset) = (header[0], header[1]);
+        let mut size = usize::from(u16::from_be_bytes([header[2], header[3]]));
+        offset += 5;
+        if size & 0x8000 != 0 {
+            // Extended dataset, the size is stored in the following bytes
+            let length = size & 0x7FFF;
+            let Some(size_bytes) = record.get(offset..offset + length) else {
+                break;
+            };
+            size = size_bytes
+                .iter()
+                .fold(0, |size, byte| (size << 8) | usize::from(*byte));
+            offset += length;
+        }
+        let Some(value) = record.get(offset..offset + size) else {
+            break;
+        };
+        values.push((record_number, dataset, value));
+        offset += size;
+    }
+
+    // CodedCharacterSet `ESC % G` marks UTF-8, older files use Latin-1
+    let is_utf8 = values
+        .iter()
+        .any(|(record, dataset, value)| (*record, *dataset) == (1, 90) && *value == b"\x1b%G");
+    let decode = |value: &[u8]| match std::str::from_utf8(value) {
+        Ok(text) => text.to_string(),
+        Err(_) if is_utf8 => String::from_utf8_lossy(value).to_string(),
+        Err(_) => value.iter().map(|byte| char::from(*byte)).collect(),
+    };
+
+    let mut datasets = BTreeMap::new();
+    for (record_number, dataset, value) in values {
+        let Some((_, name)) = DATASETS
+            .iter()
+            .find(|(number, _)| record_number == 2 && *number == dataset)
+        else {
+            continue;
+        };
+        let text = decode(value).trim_end_matches('\0').trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        match datasets.get_mut(*name) {
+            Some(IptcValue::List(items)) => items.push(text),
+            Some(IptcValue::Text(_)) => {}
+            None if *name == "Keywords" => {
+                datasets.insert(name.to_string(), IptcValue::List(vec![text]));
+            }
+            None => {
+                datasets.insert(name.to_string(), IptcValue::Text(text));
+            }
+        }
+    }
+    Iptc { datasets }
+}
+
+#[test]
+fn test_read_iptc() {
+    let dataset = |record: u8, dataset: u8, value: &[u8]| {
+        let mut bytes = vec![0x1C, record, dataset];
+        bytes.extend((value.len() as u16).to_be_bytes());
+        bytes.extend(value);
+        bytes
+    };
+    let iim = [
+        dataset(1, 90, b"\x1b%G"),
+        dataset(2, 120, "Sunset over Danube".as_bytes()),
+        dataset(2, 80, "Jane Doe".as_bytes()),
+        dataset(2, 90, "Bratislava".as_bytes()),
+        dataset(2, 25, b"river"),
+        dataset(2, 25, b"sunset"),
+    ]
+    .concat();
+
+    let mut resources = b"Photoshop 3.0\08BIM".to_vec();
+    resources.extend(IPTC_RESOURCE_ID.to_be_bytes());
+    resources.extend([0, 0]);
+    resources.extend((iim.len() as u32).to_be_bytes());
+    resources.extend(&iim);
+
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xED];
+    jpeg.extend((resources.len() as u16 + 2).to_be_bytes());
+    jpeg.extend(&resources);
+    jpeg.extend([0xFF, 0xDA, 0x00, 0x02]);
+
+    let iptc = Iptc::read(&jpeg);
+    assert_eq!(
+        iptc.get_text("Caption-Abstract"),
+        Some("Sunset over Danube".to_string())
+    );
+    assert_eq!(iptc.get_text("By-line"), Some("Jane Doe".to_string()));
+    assert_eq!(iptc.get_text("City"), Some("Bratislava".to_string()));
+    assert_eq!(iptc.get_text("Keywords"), Some("river, sunset".to_string()));
+    assert_eq!(iptc.get_text("Headline"), None);
+    assert_eq!(Iptc::read(b"not an image"), Iptc::default());
+}
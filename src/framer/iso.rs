@@ -0,0 +1,131 @@
+use exif::{Exif, In, Tag};
+use serde::Deserialize;
+
+use crate::config::Config;
+
+use super::{
+    camera::{find_camera_entry, CameraEntry},
+    formatter::format_iso,
+    text_values::get_uint,
+};
+
+/// `PhotographicSensitivity` is a SHORT, higher speeds are stored in the Exif 2.3 tags
+const SATURATED_SENSITIVITY: u32 = 65535;
+
+/// Native ISO range of the camera, speeds outside of it are marked as extended
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CameraNativeIso {
+    pub make: String,
+    pub model: String,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl CameraEntry for CameraNativeIso {
+    fn camera(&self) -> (&str, &str) {
+        (&self.make, &self.model)
+    }
+}
+
+pub fn get_iso(exif: &Exif, config: &Config) -> Option<String> {
+    let iso = get_iso_speed(exif)?;
+    let formatted = format_iso(iso, config.iso_format);
+    match config.mark_extended_iso && is_extended_iso(exif, config, iso) {
+        true => Some(format!("{} (ext)", formatted)),
+        false => Some(formatted),
+    }
+}
+
+/// Resolves the ISO speed from the tag designated by `SensitivityType`
+///
+/// Recommended exposure index is preferred as it's the value set on the camera,
+/// `PhotographicSensitivity` is used when the designated tag is missing and it isn't saturated.
+pub fn get_iso_speed(exif: &Exif) -> Option<u32> {
+    let designated_tags: &[Tag] = match get_uint(exif, Tag::SensitivityType) {
+        Some(1) => &[Tag::StandardOutputSensitivity],
+        Some(2 | 4 | 6 | 7) => &[Tag::RecommendedExposureIndex],
+        Some(3 | 5) => &[Tag::ISOSpeed],
+        _ => &[],
+    };
+    let fallback_tags = [
+        Tag::RecommendedExposureIndex,
+        Tag::ISOSpeed,
+        Tag::StandardOutputSensitivity,
+    ];
+
+    designated_tags
+        .iter()
+        .find_map(|tag| get_speed(exif, *tag))
+        .or_else(|| get_photographic_sensitivity(exif).filter(|iso| *iso < SATURATED_SENSITIVITY))
+        .or_else(|| fallback_tags.iter().find_map(|tag| get_speed(exif, *tag)))
+        .or_else(|| get_photographic_sensitivity(exif))
+}
+
+/// First non-zero value, some cameras store more of them
+fn get_photographic_sensitivity(exif: &Exif) -> Option<u32> {
+    let value = &exif
+        .get_field(Tag::PhotographicSensitivity, In::PRIMARY)?
+        .value;
+    value.iter_uint()?.find(|iso| *iso > 0)
+}
+
+fn is_extended_iso(exif: &Exif, config: &Config, iso: u32) -> bool {
+    find_camera_entry(exif, &config.camera_native_isos)
+        .is_some_and(|camera| iso < camera.min || iso > camera.max)
+}
+
+/// Speed stored in the tag, zero is stored by some cameras when it is unknown
+fn get_speed(exif: &Exif, tag: Tag) -> Option<u32> {
+    get_uint(exif, tag).filter(|speed| *speed > 0)
+}
+
+#[test]
+fn test_get_iso_speed() {
+    use exif::{experimental::Writer, Field, Value};
+    use std::io::Cursor;
+
+    let read_iso = |fields: &[Field]| {
+        let mut writer = Writer::new();
+        fields.iter().for_each(|field| writer.push_field(field));
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, false).unwrap();
+        let exif = exif::Reader::new().read_raw(buffer.into_inner()).unwrap();
+        get_iso_speed(&exif)
+    };
+    let field = |tag, value| Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value,
+    };
+
+    assert_eq!(
+        read_iso(&[field(
+            Tag::PhotographicSensitivity,
+            Value::Short(vec![0, 400])
+        )]),
+        Some(400)
+    );
+    assert_eq!(
+        read_iso(&[
+            field(Tag::PhotographicSensitivity, Value::Short(vec![65535])),
+            field(Tag::SensitivityType, Value::Short(vec![2])),
+            field(Tag::RecommendedExposureIndex, Value::Long(vec![102400])),
+        ]),
+        Some(102400)
+    );
+    assert_eq!(
+        read_iso(&[
+            field(Tag::PhotographicSensitivity, Value::Short(vec![65535])),
+            field(Tag::ISOSpeed, Value::Long(vec![204800])),
+        ]),
+        Some(204800)
+    );
+    assert_eq!(
+        read_iso(&[
+            field(Tag::PhotographicSensitivity, Value::Short(vec![100])),
+            field(Tag::SensitivityType, Value::Short(vec![1])),
+            field(Tag::StandardOutputSensitivity, Value::Long(vec![125])),
+        ]),
+        Some(125)
+    );
+}
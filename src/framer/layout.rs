@@ -0,0 +1,218 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::config::Config;
+
+use super::{
+    fields::FieldRegistry, focal_length::FocalLengthDisplay, overrides::normalize_key,
+    text_values::TextValues,
+};
+
+/// Names of the fields displayed in each group of a row, in the display order
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DisplayOrders {
+    pub left: Vec<String>,
+    pub center: Vec<String>,
+    pub right: Vec<String>,
+}
+
+impl DisplayOrders {
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.left.iter().chain(&self.center).chain(&self.right)
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        for display_order in [&mut self.left, &mut self.center, &mut self.right] {
+            display_order.retain(|displayed_name| displayed_name != name);
+        }
+    }
+}
+
+/// Rows from `[[row]]` of the config or the template manifest, a single row without them.
+/// The groups set by `--left`, `--center` and `--right` or the config replace the ones of the first row
+/// and the groups that are not set at all are built from the `show-*` options
+pub fn get_display_orders(config: &Config, text_values: &TextValues) -> Vec<DisplayOrders> {
+    let normalize = |names: &Vec<String>| -> Vec<String> {
+        names.iter().map(|name| normalize_key(name)).collect()
+    };
+    let mut rows: Vec<DisplayOrders> = config
+        .rows
+        .iter()
+        .map(|row| DisplayOrders {
+            left: normalize(&row.left),
+            center: normalize(&row.center),
+            right: normalize(&row.right),
+        })
+        .collect();
+    let Some(first_row) = rows.first_mut() else {
+        return vec![DisplayOrders {
+            left: config
+                .left
+                .as_ref()
+                .map_or_else(|| get_default_left_order(config, text_values), normalize),
+            center: config
+                .center
+                .as_ref()
+                .map_or_else(|| get_default_center_order(config), normalize),
+            right: config
+                .right
+                .as_ref()
+                .map_or_else(|| get_default_right_order(config, text_values), normalize),
+        }];
+    };
+    if let Some(left) = &config.left {
+        first_row.left = normalize(left);
+    }
+    if let Some(center) = &config.center {
+        first_row.center = normalize(center);
+    }
+    if let Some(right) = &config.right {
+        first_row.right = normalize(right);
+    }
+    rows
+}
+
+/// Whether the field is displayed by the `show-*` options or any of the configured groups
+pub fn is_displayed(config: &Config, name: &str, shown_by_default: bool) -> bool {
+    let mut configured_names = [&config.left, &config.center, &config.right]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .chain(config.rows.iter().flat_map(DisplayOrders::iter));
+    let is_configured =
+        configured_names.any(|configured_name| normalize_key(configured_name) == name);
+    // The default groups are used only for the groups and rows that are not configured
+    let uses_default_groups = config.rows.is_empty()
+        && [&config.left, &config.center, &config.right]
+            .iter()
+            .any(|group| group.is_none());
+    is_configured || (shown_by_default && uses_default_groups)
+}
+
+/// Fails on the names in the configured groups that are not registered fields
+pub fn check_display_orders(config: &Config, fields: &FieldRegistry) -> Result<()> {
+    let names = [&config.left, &config.center, &config.right]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .chain(config.rows.iter().flat_map(DisplayOrders::iter));
+    for name in names {
+        if fields.get(&normalize_key(name)).is_none() {
+            bail!("unknown field `{}` in the layout", name);
+        }
+    }
+    Ok(())
+}
+
+fn get_default_left_order(config: &Config, text_values: &TextValues) -> Vec<String> {
+    let mut display_order = vec!["camera", "lens"];
+    if text_values.get("film_stock").is_some() {
+        display_order.push("film_stock");
+    }
+    if config.show_maker_note {
+        // Only one of the vendor specific looks is stored by the camera
+        display_order.extend(
+            ["film_simulation", "picture_style", "creative_look"]
+                .into_iter()
+                .filter(|name| text_values.get(name).is_some()),
+        );
+    }
+    if config.show_date {
+        display_order.push("date_taken");
+    }
+    if config.show_location {
+        display_order.push("location");
+    }
+    if config.show_gps {
+        display_order.extend(["latitude", "longitude", "altitude", "direction"]);
+    }
+    display_order.into_iter().map(str::to_string).collect()
+}
+
+fn get_default_center_order(config: &Config) -> Vec<String> {
+    match config.show_signature {
+        true => vec!["signature".to_string()],
+        false => vec![],
+    }
+}
+
+fn get_default_right_order(config: &Config, text_values: &TextValues) -> Vec<String> {
+    let mut display_order = vec!["aperture"];
+    if config.show_equivalent_aperture {
+        display_order.push("equivalent_aperture");
+    }
+    display_order.extend([
+        "shutter_speed",
+        match config.focal_length_display {
+            FocalLengthDisplay::Equivalent => "equivalent_focal_length",
+            _ => "focal_length",
+        },
+        "iso",
+    ]);
+    if config.show_exposure {
+        display_order.extend([
+            "exposure_bias",
+            "exposure_program",
+            "metering_mode",
+            "flash",
+            "white_balance",
+        ]);
+    }
+    if config.show_maker_note && text_values.get("shutter_count").is_some() {
+        display_order.push("shutter_count");
+    }
+    display_order.into_iter().map(str::to_string).collect()
+}
+
+#[test]
+fn test_get_display_orders() {
+    let text_values = TextValues::from([("camera", "X-T30"), ("shutter_count", "12345")]);
+    let mut config = Config {
+        show_signature: true,
+        ..Config::default()
+    };
+    let display_orders = &get_display_orders(&config, &text_values)[0];
+    assert_eq!(display_orders.left, vec!["camera", "lens"]);
+    assert_eq!(display_orders.center, vec!["signature"]);
+    assert_eq!(
+        display_orders.right,
+        vec!["aperture", "shutter_speed", "focal_length", "iso"]
+    );
+
+    config.left = Some(vec!["Lens".to_string(), "camera".to_string()]);
+    config.center = Some(vec![]);
+    config.right = Some(vec!["shutter-count".to_string()]);
+    let display_orders = &get_display_orders(&config, &text_values)[0];
+    assert_eq!(display_orders.left, vec!["lens", "camera"]);
+    assert!(display_orders.center.is_empty());
+    assert_eq!(display_orders.right, vec!["shutter_count"]);
+
+    assert!(is_displayed(&config, "shutter_count", false));
+    assert!(!is_displayed(&config, "location", true));
+
+    let fields = FieldRegistry::load(&config);
+    assert!(check_display_orders(&config, &fields).is_ok());
+    config.right = Some(vec!["shutter".to_string()]);
+    assert!(check_display_orders(&config, &fields).is_err());
+
+    config.right = None;
+    config.rows = vec![
+        DisplayOrders {
+            left: vec!["camera".to_string()],
+            right: vec!["date-taken".to_string()],
+            ..DisplayOrders::default()
+        },
+        DisplayOrders {
+            center: vec!["aperture".to_string(), "iso".to_string()],
+            ..DisplayOrders::default()
+        },
+    ];
+    let rows = get_display_orders(&config, &text_values);
+    assert_eq!(rows.len(), 2);
+    // `left` of the config replaces the left group of the first row
+    assert_eq!(rows[0].left, vec!["lens", "camera"]);
+    assert_eq!(rows[0].right, vec!["date_taken"]);
+    assert_eq!(rows[1].center, vec!["aperture", "iso"]);
+    assert!(check_display_orders(&config, &fields).is_ok());
+}
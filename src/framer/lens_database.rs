@@ -0,0 +1,143 @@
+use log::{debug, warn};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+use super::makernote::Vendor;
+
+/// Lens name for the numeric lens ID stored by the vendor in the maker note
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct LensId {
+    pub vendor: Vendor,
+    pub id: u32,
+    pub name: String,
+}
+
+/// Bundled lens IDs extended by `[[lens-id]]` entries from the config file
+pub struct LensDatabase {
+    lenses: Vec<LensId>,
+    focal_range: Regex,
+}
+
+impl LensDatabase {
+    pub fn load(config: &Config) -> LensDatabase {
+        // Entries from the config come first so they take precedence over the bundled ones
+        let lenses = [
+            config.lens_ids.clone(),
+            parse_lens_ids(include_str!("../../data/lens_ids.txt")),
+        ]
+        .concat();
+        debug!("Loaded {} lens IDs", lenses.len());
+        LensDatabase {
+            lenses,
+            focal_range: Regex::new(r"(\d+(?:\.\d+)?)(?:-(\d+(?:\.\d+)?))?mm").unwrap(),
+        }
+    }
+
+    /// Name of the lens, the focal range in millimeters picks one of the lenses sharing the ID
+    pub fn get_name(
+        &self,
+        vendor: Vendor,
+        id: u32,
+        focal_range: Option<(f64, f64)>,
+    ) -> Option<String> {
+        let mut candidates = self
+            .lenses
+            .iter()
+            .filter(|lens| lens.vendor == vendor && lens.id == id)
+            .peekable();
+        let first = candidates.peek().copied()?;
+        let lens = match focal_range {
+            Some(focal_range) => candidates
+                .find(|lens| self.has_focal_range(&lens.name, focal_range))
+                .unwrap_or(first),
+            None => first,
+        };
+        Some(lens.name.clone())
+    }
+
+    /// Compares the range with the one in the name like "17-40mm" or "50mm"
+    fn has_focal_range(&self, name: &str, (min, max): (f64, f64)) -> bool {
+        let Some(captures) = self.focal_range.captures(name) else {
+            return false;
+        };
+        let parse = |index| captures.get(index)?.as_str().parse::<f64>().ok();
+        let (Some(name_min), name_max) = (parse(1), parse(2)) else {
+            return false;
+        };
+        let name_max = name_max.unwrap_or(name_min);
+        (name_min - min).abs() < 0.5 && (name_max - max).abs() < 0.5
+    }
+}
+
+/// Tab separated vendor, lens ID and name, lines starting with `#` are comments
+fn parse_lens_ids(content: &str) -> Vec<LensId> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|(index, line)| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            let lens_id = (|| {
+                Some(LensId {
+                    vendor: columns.first()?.parse().ok()?,
+                    id: columns.get(1)?.parse().ok()?,
+                    name: columns.get(2)?.to_string(),
+                })
+            })();
+            if lens_id.is_none() {
+                warn!("invalid lens ID on line {}: `{}`", index + 1, line);
+            }
+            lens_id
+        })
+        .collect()
+}
+
+#[test]
+fn test_get_name() {
+    let mut config = Config::default();
+    config.lens_ids.push(LensId {
+        vendor: Vendor::Canon,
+        id: 1,
+        name: "Sigma 50mm f/2.8 EX DG Macro".to_string(),
+    });
+    let lenses = LensDatabase::load(&config);
+
+    assert_eq!(
+        lenses.get_name(Vendor::Canon, 4156, None),
+        Some("Canon EF 50mm f/1.8 STM".to_string())
+    );
+    assert_eq!(
+        lenses.get_name(Vendor::Canon, 1, None),
+        Some("Sigma 50mm f/2.8 EX DG Macro".to_string())
+    );
+    assert_eq!(lenses.get_name(Vendor::Sony, 1, None), None);
+
+    // Lenses sharing the ID are told apart by the focal range
+    assert_eq!(
+        lenses.get_name(Vendor::Canon, 137, None),
+        Some("Canon EF 85mm f/1.2L USM".to_string())
+    );
+    assert_eq!(
+        lenses.get_name(Vendor::Canon, 137, Some((17., 50.))),
+        Some("Sigma 17-50mm f/2.8 OS HSM".to_string())
+    );
+    assert_eq!(
+        lenses.get_name(Vendor::Canon, 137, Some((300., 300.))),
+        Some("Canon EF 85mm f/1.2L USM".to_string())
+    );
+}
+
+#[test]
+fn test_parse_lens_ids() {
+    let lenses = parse_lens_ids(
+        "# comment\ncanon\t1\tCanon EF 50mm f/1.8\n\nleica\t1\tSummicron\nsony\tx\tSony FE\n",
+    );
+    assert_eq!(lenses.len(), 1);
+    assert_eq!(lenses[0].name, "Canon EF 50mm f/1.8");
+    // Every line of the bundled table is valid
+    let content = include_str!("../../data/lens_ids.txt");
+    let lines = content.lines().filter(|line| !line.starts_with('#'));
+    assert_eq!(parse_lens_ids(content).len(), lines.count());
+}
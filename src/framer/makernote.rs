@@ -0,0 +1,610 @@
+use exif::{Exif, In, Rational, Tag, Value};
+use serde::Deserialize;
+use std::{collections::BTreeMap, str::FromStr};
+
+use super::lens_database::LensDatabase;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Vendor {
+    Canon,
+    Nikon,
+    Sony,
+    Olympus,
+    Panasonic,
+    Fujifilm,
+}
+
+impl FromStr for Vendor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value.trim().to_lowercase().as_str() {
+            "canon" => Vendor::Canon,
+            "nikon" => Vendor::Nikon,
+            "sony" => Vendor::Sony,
+            "olympus" | "om system" => Vendor::Olympus,
+            "panasonic" => Vendor::Panasonic,
+            "fujifilm" => Vendor::Fujifilm,
+            _ => return Err(format!("unknown lens vendor `{}`", value)),
+        })
+    }
+}
+
+impl TryFrom<String> for Vendor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, String> {
+        value.parse()
+    }
+}
+
+/// Maker note IFD decoded into plain `exif::Value`s
+///
+/// Every vendor stores its maker note a bit differently (with or without a header,
+/// offsets relative to the TIFF header or to the maker note itself), this struct
+/// hides these differences so the getters only need to know the tag numbers.
+pub struct MakerNote {
+    pub vendor: Vendor,
+    entries: Vec<(u16, Value)>,
+    sub_ifds: Vec<(u16, MakerNote)>,
+}
+
+const OLYMPUS_SUB_IFDS: [u16; 6] = [0x2010, 0x2020, 0x2030, 0x2040, 0x2050, 0x3000];
+
+struct IfdReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl MakerNote {
+    pub fn parse(exif: &Exif) -> Option<MakerNote> {
+        let field = exif.get_field(Tag::MakerNote, In::PRIMARY)?;
+        let (bytes, offset) = match &field.value {
+            Value::Undefined(bytes, offset) => (bytes.as_slice(), *offset as usize),
+            _ => return None,
+        };
+        let tiff = IfdReader {
+            data: exif.buf(),
+            little_endian: exif.little_endian(),
+        };
+
+        if bytes.starts_with(b"Nikon\0") && bytes.len() > 18 {
+            // Type 3 Nikon maker note contains its own TIFF header
+            let embedded = &bytes[10..];
+            let reader = IfdReader {
+                data: embedded,
+                little_endian: &embedded[0..2] == b"II",
+            };
+            let ifd_offset = reader.u32_at(4)? as usize;
+            return reader.read_ifd(Vendor::Nikon, ifd_offset, 0);
+        }
+        if bytes.starts_with(b"SONY DSC \0") || bytes.starts_with(b"SONY CAM \0") {
+            return tiff.read_ifd(Vendor::Sony, offset + 12, 0);
+        }
+        if bytes.starts_with(b"Panasonic\0") {
+            return tiff.read_ifd(Vendor::Panasonic, offset + 12, 0);
+        }
+        if bytes.starts_with(b"OLYMPUS\0") && bytes.len() > 12 {
+            let reader = IfdReader {
+                data: bytes,
+                little_endian: &bytes[8..10] == b"II",
+            };
+            return reader.read_ifd(Vendor::Olympus, 12, 0);
+        }
+        if bytes.starts_with(b"OM SYSTEM\0") && bytes.len() > 16 {
+            let reader = IfdReader {
+                data: bytes,
+                little_endian: &bytes[12..14] == b"II",
+            };
+            return reader.read_ifd(Vendor::Olympus, 16, 0);
+        }
+        if bytes.starts_with(b"FUJIFILM") && bytes.len() > 12 {
+            let reader = IfdReader {
+                data: bytes,
+                little_endian: true,
+            };
+            let ifd_offset = reader.u32_at(8)? as usize;
+            return reader.read_ifd(Vendor::Fujifilm, ifd_offset, 0);
+        }
+
+        let make = exif
+            .get_field(Tag::Make, In::PRIMARY)?
+            .display_value()
+            .to_string()
+            .to_lowercase();
+        if make.contains("canon") {
+            // Canon maker note is a plain IFD with offsets relative to the TIFF header
+            return tiff.read_ifd(Vendor::Canon, offset, 0);
+        }
+        None
+    }
+
+    pub fn get(&self, tag: u16) -> Option<&Value> {
+        self.entries
+            .iter()
+            .find(|(entry_tag, _)| *entry_tag == tag)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_sub_ifd(&self, tag: u16) -> Option<&MakerNote> {
+        self.sub_ifds
+            .iter()
+            .find(|(entry_tag, _)| *entry_tag == tag)
+            .map(|(_, ifd)| ifd)
+    }
+
+    pub fn get_ascii(&self, tag: u16) -> Option<String> {
+        match self.get(tag)? {
+            Value::Ascii(values) => values
+                .first()
+                .map(|value| String::from_utf8_lossy(value).trim().to_string())
+                .filter(|value| !value.is_empty()),
+            _ => None,
+        }
+    }
+
+    pub fn get_uint(&self, tag: u16, index: usize) -> Option<u32> {
+        self.get(tag)?.get_uint(index)
+    }
+}
+
+pub fn get_lens(maker_note: &MakerNote, lenses: &LensDatabase) -> Option<String> {
+    match maker_note.vendor {
+        Vendor::Canon => maker_note.get_ascii(0x0095).or_else(|| {
+            let focal_range = get_focal_range(maker_note);
+            lenses.get_name(Vendor::Canon, get_lens_id(maker_note)?, focal_range)
+        }),
+        Vendor::Nikon => match maker_note.get(0x0084)? {
+            Value::Rational(lens) if lens.len() == 4 => format_lens_specification(lens),
+            _ => None,
+        },
+        Vendor::Sony => lenses.get_name(Vendor::Sony, get_lens_id(maker_note)?, None),
+        Vendor::Olympus => maker_note.get_sub_ifd(0x2010)?.get_ascii(0x0203),
+        Vendor::Panasonic => maker_note.get_ascii(0x0051),
+        Vendor::Fujifilm => None,
+    }
+}
+
+/// Shortest and longest focal length of the lens in millimeters
+fn get_focal_range(maker_note: &MakerNote) -> Option<(f64, f64)> {
+    match maker_note.vendor {
+        // MaxFocalLength, MinFocalLength and FocalUnits per mm of CameraSettings
+        Vendor::Canon => {
+            let units = f64::from(maker_note.get_uint(0x0001, 25).filter(|units| *units > 0)?);
+            let max = f64::from(maker_note.get_uint(0x0001, 23)?) / units;
+            let min = f64::from(maker_note.get_uint(0x0001, 24)?) / units;
+            Some((min, max)).filter(|(min, max)| *min > 0. && min <= max)
+        }
+        _ => None,
+    }
+}
+
+/// Vendor specific fields available to the templates under `maker_note`
+pub fn get_maker_note_fields(
+    maker_note: &MakerNote,
+    lenses: &LensDatabase,
+) -> BTreeMap<&'static str, String> {
+    [
+        ("Vendor", Some(format!("{:?}", maker_note.vendor))),
+        ("Lens", get_lens(maker_note, lenses)),
+        (
+            "LensId",
+            get_lens_id(maker_note).map(|lens_id| lens_id.to_string()),
+        ),
+        ("FilmSimulation", get_film_simulation(maker_note)),
+        ("PictureStyle", get_picture_style(maker_note)),
+        ("CreativeLook", get_creative_look(maker_note)),
+        (
+            "ShutterCount",
+            get_shutter_count(maker_note).map(|count| count.to_string()),
+        ),
+        (
+            "ImageCount",
+            get_image_count(maker_note).map(|count| count.to_string()),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((name, value?)))
+    .collect()
+}
+
+/// Lens type number the vendors use instead of the lens name
+pub fn get_lens_id(maker_note: &MakerNote) -> Option<u32> {
+    match maker_note.vendor {
+        // LensType is the 22nd value of CameraSettings
+        Vendor::Canon => maker_note.get_uint(0x0001, 22),
+        Vendor::Sony => maker_note.get_uint(0xb027, 0),
+        _ => None,
+    }
+    .filter(|lens_id| *lens_id != 0 && *lens_id != 0xffff)
+}
+
+/// Fujifilm film simulation, the monochrome ones are stored as the saturation
+pub fn get_film_simulation(maker_note: &MakerNote) -> Option<String> {
+    if maker_note.vendor != Vendor::Fujifilm {
+        return None;
+    }
+    let film_mode = maker_note.get_uint(0x1401, 0).and_then(|film_mode| {
+        Some(match film_mode {
+            0x000 => "Provia/Standard",
+            0x120 => "Astia/Soft",
+            0x200 | 0x400 => "Velvia/Vivid",
+            0x500 => "Pro Neg. Std",
+            0x501 => "Pro Neg. Hi",
+            0x600 => "Classic Chrome",
+            0x700 => "Eterna/Cinema",
+            0x800 => "Classic Neg.",
+            0x900 => "Eterna Bleach Bypass",
+            0xa00 => "Nostalgic Neg.",
+            0xb00 => "Reala Ace",
+            _ => return None,
+        })
+    });
+    let monochrome = || {
+        Some(match maker_note.get_uint(0x1003, 0)? {
+            0x300 => "Monochrome",
+            0x301 => "Monochrome + R",
+            0x302 => "Monochrome + Ye",
+            0x303 => "Monochrome + G",
+            0x310 => "Sepia",
+            0x500 => "Acros",
+            0x501 => "Acros + R",
+            0x502 => "Acros + Ye",
+            0x503 => "Acros + G",
+            _ => return None,
+        })
+    };
+    film_mode.or_else(monochrome).map(str::to_string)
+}
+
+/// Canon picture style, Nikon picture control, Olympus picture mode or Panasonic photo style
+pub fn get_picture_style(maker_note: &MakerNote) -> Option<String> {
+    let picture_style = match maker_note.vendor {
+        // PictureStyle is the 10th value of ProcessingInfo
+        Vendor::Canon => match maker_note.get_uint(0x00a0, 10)? {
+            0x01 | 0x81 => "Standard",
+            0x02 | 0x82 => "Portrait",
+            0x03 => "High Saturation",
+            0x04 => "Adobe RGB",
+            0x05 => "Low Saturation",
+            0x83 => "Landscape",
+            0x84 => "Neutral",
+            0x85 => "Faithful",
+            0x86 => "Monochrome",
+            0x87 => "Auto",
+            0x88 => "Fine Detail",
+            0x21..=0x23 => "User Defined",
+            _ => return None,
+        },
+        Vendor::Nikon => return get_nikon_picture_control(maker_note),
+        Vendor::Olympus => match maker_note.get_sub_ifd(0x2020)?.get_uint(0x0520, 0)? {
+            1 => "Vivid",
+            2 => "Natural",
+            3 => "Muted",
+            4 => "Portrait",
+            5 => "i-Enhance",
+            6 => "e-Portrait",
+            7 => "Color Creator",
+            9..=11 => "Color Profile",
+            12..=14 | 18 => "Monochrome Profile",
+            17 => "Art Filter",
+            256 => "Monotone",
+            512 => "Sepia",
+            _ => return None,
+        },
+        Vendor::Panasonic => match maker_note.get_uint(0x0089, 0)? {
+            1 => "Standard",
+            2 => "Vivid",
+            3 => "Natural",
+            4 => "Monochrome",
+            5 => "Scenery",
+            6 => "Portrait",
+            8 => "Cinelike D",
+            9 => "Cinelike V",
+            11 => "L. Monochrome",
+            12 => "Like709",
+            15 => "L. Monochrome D",
+            17 => "V-Log",
+            18 => "Cinelike D2",
+            _ => return None,
+        },
+        Vendor::Sony | Vendor::Fujifilm => return None,
+    };
+    Some(picture_style.to_string())
+}
+
+/// Name stored in `PictureControlData` after its version, e.g. "0310"
+fn get_nikon_picture_control(maker_note: &MakerNote) -> Option<String> {
+    let Value::Undefined(data, _) = maker_note.get(0x0023)? else {
+        return None;
+    };
+    let name_offset = match data.get(0..2)? {
+        b"03" => 8,
+        _ => 4,
+    };
+    let name = data.get(name_offset..name_offset + 20)?;
+    let name = String::from_utf8_lossy(name.split(|byte| *byte == 0).next()?);
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    // Upper case names like "STANDARD" are turned to "Standard"
+    Some(first.to_string() + &chars.as_str().to_lowercase())
+}
+
+/// Sony creative style or creative look of the newer cameras
+pub fn get_creative_look(maker_note: &MakerNote) -> Option<String> {
+    if maker_note.vendor != Vendor::Sony {
+        return None;
+    }
+    let style = maker_note.get_ascii(0xb020)?;
+    Some(
+        match style.as_str() {
+            "ST" => "Standard",
+            "PT" => "Portrait",
+            "NT" => "Neutral",
+            "VV" => "Vivid",
+            "VV2" => "Vivid 2",
+            "BW" => "B&W",
+            "SE" => "Sepia",
+            "AdobeRGB" => "Adobe RGB",
+            "Nightview" => "Night View",
+            // FL, IN and SH looks don't have a longer name
+            style => style,
+        }
+        .to_string(),
+    )
+}
+
+/// Number of the shutter actuations, only Nikon stores it in a plain tag
+///
+/// Canon, Sony, Olympus and Panasonic keep it in encrypted or model specific binary blocks
+/// that are not decoded, Fujifilm stores only the image count.
+pub fn get_shutter_count(maker_note: &MakerNote) -> Option<u32> {
+    match maker_note.vendor {
+        Vendor::Nikon => maker_note.get_uint(0x00a7, 0),
+        _ => None,
+    }
+    .filter(|count| *count > 0)
+}
+
+/// Number of the images taken by Fujifilm cameras, it doesn't count the discarded shots
+pub fn get_image_count(maker_note: &MakerNote) -> Option<u32> {
+    match maker_note.vendor {
+        Vendor::Fujifilm => maker_note.get_uint(0x1438, 0).map(|count| count & 0x7fff),
+        _ => None,
+    }
+    .filter(|count| *count > 0)
+}
+
+/// Formats min/max focal length and min/max aperture as "18-55mm f/3.5-5.6"
+pub fn format_lens_specification(lens: &[Rational]) -> Option<String> {
+    let values: Vec<f64> = lens.iter().map(|value| value.to_f64()).collect();
+    if values.len() != 4
+        || values
+            .iter()
+            .any(|value| !value.is_finite() || *value <= 0.)
+    {
+        return None;
+    }
+    let range = |min: f64, max: f64| {
+        if min == max {
+            format!("{}", min)
+        } else {
+            format!("{}-{}", min, max)
+        }
+    };
+    Some(format!(
+        "{}mm f/{}",
+        range(values[0], values[1]),
+        range(values[2], values[3])
+    ))
+}
+
+impl IfdReader<'_> {
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn read_ifd(&self, vendor: Vendor, offset: usize, depth: u8) -> Option<MakerNote> {
+        let count = self.u16_at(offset)? as usize;
+        let mut maker_note = MakerNote {
+            vendor,
+            entries: vec![],
+            sub_ifds: vec![],
+        };
+
+        for index in 0..count {
+            let entry = offset + 2 + index * 12;
+            let (Some(tag), Some(typ), Some(count)) = (
+                self.u16_at(entry),
+                self.u16_at(entry + 2),
+                self.u32_at(entry + 4),
+            ) else {
+                break;
+            };
+            // IFD (13) typed entries point to nested IFDs, only follow one level deep
+            if typ == 13 && depth == 0 {
+                if let Some(sub_ifd) = self
+                    .u32_at(entry + 8)
+                    .and_then(|sub_offset| self.read_ifd(vendor, sub_offset as usize, depth + 1))
+                {
+                    maker_note.sub_ifds.push((tag, sub_ifd));
+                }
+                continue;
+            }
+            if let Some(value) = self.read_value(typ, count as usize, entry + 8) {
+                // Olympus stores some sub IFDs as plain LONG offsets
+                if vendor == Vendor::Olympus && depth == 0 && OLYMPUS_SUB_IFDS.contains(&tag) {
+                    if let Some(sub_ifd) = value
+                        .get_uint(0)
+                        .and_then(|sub_offset| self.read_ifd(vendor, sub_offset as usize, 1))
+                    {
+                        maker_note.sub_ifds.push((tag, sub_ifd));
+                        continue;
+                    }
+                }
+                maker_note.entries.push((tag, value));
+            }
+        }
+
+        Some(maker_note)
+    }
+
+    fn read_value(&self, typ: u16, count: usize, value_offset: usize) -> Option<Value> {
+        let unit_size = match typ {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => return None,
+        };
+        let size = unit_size * count;
+        let start = if size <= 4 {
+            value_offset
+        } else {
+            self.u32_at(value_offset)? as usize
+        };
+        let bytes = self.data.get(start..start.checked_add(size)?)?;
+
+        Some(match typ {
+            1 => Value::Byte(bytes.to_vec()),
+            2 => Value::Ascii(
+                bytes
+                    .split(|byte| *byte == 0)
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.to_vec())
+                    .collect(),
+            ),
+            3 => Value::Short(
+                (0..count)
+                    .filter_map(|index| self.u16_at(start + index * 2))
+                    .collect(),
+            ),
+            4 => Value::Long(
+                (0..count)
+                    .filter_map(|index| self.u32_at(start + index * 4))
+                    .collect(),
+            ),
+            5 => Value::Rational(
+                (0..count)
+                    .filter_map(|index| {
+                        Some(Rational {
+                            num: self.u32_at(start + index * 8)?,
+                            denom: self.u32_at(start + index * 8 + 4)?,
+                        })
+                    })
+                    .collect(),
+            ),
+            8 => Value::SShort(
+                (0..count)
+                    .filter_map(|index| self.u16_at(start + index * 2).map(|value| value as i16))
+                    .collect(),
+            ),
+            9 => Value::SLong(
+                (0..count)
+                    .filter_map(|index| self.u32_at(start + index * 4).map(|value| value as i32))
+                    .collect(),
+            ),
+            _ => Value::Undefined(bytes.to_vec(), start as u32),
+        })
+    }
+}
+
+#[test]
+fn test_format_lens_specification() {
+    let zoom = [
+        Rational { num: 18, denom: 1 },
+        Rational { num: 55, denom: 1 },
+        Rational { num: 35, denom: 10 },
+        Rational { num: 56, denom: 10 },
+    ];
+    assert_eq!(
+        format_lens_specification(&zoom),
+        Some("18-55mm f/3.5-5.6".to_string())
+    );
+
+    let prime = [
+        Rational { num: 50, denom: 1 },
+        Rational { num: 50, denom: 1 },
+        Rational { num: 18, denom: 10 },
+        Rational { num: 18, denom: 10 },
+    ];
+    assert_eq!(
+        format_lens_specification(&prime),
+        Some("50mm f/1.8".to_string())
+    );
+}
+
+#[test]
+fn test_get_maker_note_fields() {
+    let fujifilm = MakerNote {
+        vendor: Vendor::Fujifilm,
+        entries: vec![
+            (0x1401, Value::Short(vec![0x800])),
+            (0x1438, Value::Long(vec![0x8000 + 1234])),
+        ],
+        sub_ifds: vec![],
+    };
+    assert_eq!(
+        get_film_simulation(&fujifilm),
+        Some("Classic Neg.".to_string())
+    );
+    assert_eq!(get_shutter_count(&fujifilm), None);
+    assert_eq!(get_image_count(&fujifilm), Some(1234));
+
+    let acros = MakerNote {
+        vendor: Vendor::Fujifilm,
+        entries: vec![(0x1003, Value::Short(vec![0x501]))],
+        sub_ifds: vec![],
+    };
+    assert_eq!(get_film_simulation(&acros), Some("Acros + R".to_string()));
+
+    let mut picture_control = b"0310".to_vec();
+    picture_control.extend(b"\0\0\0\0STANDARD\0\0\0\0\0\0\0\0\0\0\0\0");
+    let nikon = MakerNote {
+        vendor: Vendor::Nikon,
+        entries: vec![
+            (0x0023, Value::Undefined(picture_control, 0)),
+            (0x00a7, Value::Long(vec![48213])),
+        ],
+        sub_ifds: vec![],
+    };
+    let lenses = LensDatabase::load(&crate::config::Config::default());
+    let fields = get_maker_note_fields(&nikon, &lenses);
+    assert_eq!(fields["PictureStyle"], "Standard");
+    assert_eq!(fields["ShutterCount"], "48213");
+    assert_eq!(fields["Vendor"], "Nikon");
+    assert!(!fields.contains_key("FilmSimulation"));
+
+    let sony = MakerNote {
+        vendor: Vendor::Sony,
+        entries: vec![
+            (0xb020, Value::Ascii(vec![b"VV2".to_vec()])),
+            (0xb027, Value::Long(vec![32824])),
+        ],
+        sub_ifds: vec![],
+    };
+    assert_eq!(get_creative_look(&sony), Some("Vivid 2".to_string()));
+    assert_eq!(
+        get_lens(&sony, &lenses),
+        Some("Sony FE 50mm F1.8".to_string())
+    );
+}
@@ -0,0 +1,22 @@
+use std::collections::BTreeMap;
+
+use exif::Exif;
+
+use super::{date_time::DateTaken, gps::Position, iptc::Iptc, makernote::MakerNote, xmp::Xmp};
+
+/// Metadata of a single image the text values are extracted from
+pub struct Metadata<'a> {
+    pub exif: &'a Exif,
+    /// Embedded XMP merged with the sidecar
+    pub xmp: &'a Xmp,
+    pub iptc: &'a Iptc,
+    pub maker_note: Option<&'a MakerNote>,
+    /// Capture time already corrected by the time shift
+    pub date_taken: Option<DateTaken>,
+    /// Position from EXIF GPS or from the GPX track
+    pub position: Option<Position>,
+    /// Crop factor shared by the focal length and the equivalent values
+    pub crop_factor: Option<f64>,
+    /// Fields decoded from the maker note keyed by their name like `FilmSimulation`
+    pub maker_note_fields: BTreeMap<&'static str, String>,
+}
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, bail};
+use serde::Deserialize;
+use std::str::FromStr;
+
+use crate::config::Config;
+
+use super::{fields::FieldRegistry, text_values::TextValues};
+
+/// What to do with the value missing in the metadata
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum MissingValue {
+    /// Text displayed instead of the value
+    Placeholder(String),
+    /// Leaves out the value together with its icon
+    Hide,
+    /// Fails the processing of the image
+    Error,
+}
+
+impl FromStr for MissingValue {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "hide" => MissingValue::Hide,
+            "error" => MissingValue::Error,
+            placeholder => MissingValue::Placeholder(placeholder.to_string()),
+        })
+    }
+}
+
+impl TryFrom<String> for MissingValue {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, String> {
+        value.parse()
+    }
+}
+
+/// Policy configured for the field, falling back to the global one
+pub fn get_missing_value<'a>(config: &'a Config, name: &str) -> &'a MissingValue {
+    config
+        .missing_values
+        .get(name)
+        .unwrap_or(&config.missing_value)
+}
+
+/// Fills placeholders of the displayed values that are missing and fails when one of them is required
+///
+/// Hidden values and the ones that are not displayed stay empty so the templates can test them with `#if`.
+pub fn apply_missing_values(
+    text_values: &mut TextValues,
+    displayed_names: &[&str],
+    config: &Config,
+) -> Result<(), anyhow::Error> {
+    for name in displayed_names {
+        if text_values.get(name).is_some() {
+            continue;
+        }
+        match get_missing_value(config, name) {
+            MissingValue::Placeholder(placeholder) => {
+                text_values.set(name, Some(placeholder.clone()))
+            }
+            MissingValue::Hide => {}
+            MissingValue::Error => return Err(anyhow!("`{}` is missing in the metadata", name)),
+        }
+    }
+    Ok(())
+}
+
+/// Fails on the names in `missing-values` that are not registered fields
+pub fn check_missing_values(config: &Config, fields: &FieldRegistry) -> Result<(), anyhow::Error> {
+    for name in config.missing_values.keys() {
+        if fields.get(name).is_none() {
+            bail!("unknown field `{}` in `missing-values`", name);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_apply_missing_values() {
+    let mut config = Config::default();
+    config
+        .missing_values
+        .insert("lens".to_string(), MissingValue::Hide);
+    let mut text_values = TextValues::from([("camera", "Fujifilm X-T30")]);
+    apply_missing_values(&mut text_values, &["camera", "lens", "iso"], &config).unwrap();
+    assert_eq!(text_values.get("camera").unwrap(), "Fujifilm X-T30");
+    assert_eq!(text_values.get("lens"), None);
+    assert_eq!(text_values.get("iso").unwrap(), "N/A");
+    // Not displayed in the layout
+    assert_eq!(text_values.get("title"), None);
+
+    config
+        .missing_values
+        .insert("iso".to_string(), MissingValue::Error);
+    let mut text_values = TextValues::default();
+    assert!(apply_missing_values(&mut text_values, &["lens"], &config).is_ok());
+    assert!(apply_missing_values(&mut text_values, &["iso"], &config).is_err());
+    assert_eq!("hide".parse(), Ok(MissingValue::Hide));
+    assert_eq!("-".parse(), Ok(MissingValue::Placeholder("-".to_string())));
+
+    let fields = FieldRegistry::load(&config);
+    assert!(check_missing_values(&config, &fields).is_ok());
+    config
+        .missing_values
+        .insert("shuter_count".to_string(), MissingValue::Error);
+    assert!(check_missing_values(&config, &fields).is_err());
+}
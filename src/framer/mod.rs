@@ -1,20 +1,110 @@
+pub mod camera;
+pub mod date_time;
+pub mod exif_values;
+pub mod exposure;
+pub mod fields;
+pub mod focal_length;
+pub mod font;
+pub mod formatter;
+pub mod geocoding;
+pub mod gps;
+pub mod gpx;
+pub mod iptc;
+pub mod iso;
+pub mod layout;
+pub mod lens_database;
+pub mod makernote;
+pub mod metadata;
+pub mod missing_value;
+pub mod overrides;
 pub mod positions;
+pub mod profile;
+pub mod signature;
 pub mod text_values;
+pub mod xmp;
+
+use std::collections::BTreeMap;
 
 use anyhow::Result;
+use chrono::Utc;
 use exif::Exif;
+use log::debug;
 use serde::Serialize;
 
+use crate::config::Config;
+
 use self::{
-    positions::{get_positions, PositionedValue},
-    text_values::{get_text_values, TextValuesKeys},
+    date_time::{get_date_taken, get_time_shift, DateTaken},
+    exif_values::{get_exif_values, ExifValues},
+    fields::FieldRegistry,
+    focal_length::get_crop_factor,
+    font::Font,
+    geocoding::Geocoder,
+    gps::{get_position, Position},
+    gpx::Track,
+    iptc::Iptc,
+    layout::{get_display_orders, DisplayOrders},
+    lens_database::LensDatabase,
+    makernote::{get_maker_note_fields, MakerNote},
+    metadata::Metadata,
+    missing_value::apply_missing_values,
+    overrides::Overrides,
+    positions::{get_positions, get_row_count, PositionedValue},
+    text_values::{get_text_values, TextValues},
+    xmp::Xmp,
 };
 
-pub struct FrameSettings {
+/// Data loaded once and shared by all processed images
+pub struct Resources {
+    /// Loaded only when the location is displayed
+    pub geocoder: Option<Geocoder>,
+    pub fields: FieldRegistry,
+    pub lenses: LensDatabase,
+    pub track: Option<Track>,
+    /// Font declared by the template, the width of the text is estimated without it
+    pub font: Option<Font>,
+}
+
+/// Size of the font in the default template the `letter_width` estimate corresponds to
+const DEFAULT_FONT_SIZE: f64 = 18.;
+
+#[derive(Clone, Copy)]
+pub struct FrameSettings<'a> {
     letter_width: i32,
     inner_border: i32,
     outer_border: i32,
     icon_size: i32,
+    font: Option<&'a Font>,
+    /// Scale of the text and the icons shrunk to fit the values into the frame
+    scale: f64,
+    row_height: i32,
+}
+
+impl<'a> FrameSettings<'a> {
+    /// Settings with the text, the icons and the gaps between them scaled down
+    fn get_scaled(&self, scale: f64) -> FrameSettings<'a> {
+        let scale_size = |size: i32| (size as f64 * scale).round() as i32;
+        FrameSettings {
+            inner_border: scale_size(self.inner_border),
+            outer_border: scale_size(self.outer_border),
+            icon_size: scale_size(self.icon_size),
+            scale: self.scale * scale,
+            ..*self
+        }
+    }
+
+    /// Width of the text measured with the font or estimated from the `letter_width`
+    fn get_text_width(&self, text: &str) -> i32 {
+        match self.font {
+            Some(font) => (font.measure(text) * self.scale).ceil() as i32,
+            None => (text.len() as f64 * self.letter_width as f64 * self.scale).ceil() as i32,
+        }
+    }
+
+    fn get_font_size(&self) -> f64 {
+        let font_size = self.font.map_or(DEFAULT_FONT_SIZE, |font| font.size) * self.scale;
+        (font_size * 10.).round() / 10.
+    }
 }
 
 #[derive(Serialize)]
@@ -22,34 +112,88 @@ pub struct FrameData {
     pub width: u32,
     pub height: u32,
     pub values: Vec<PositionedValue>,
+    pub exif: ExifValues,
+    /// XMP properties keyed by the prefixed name like `dc:title`
+    pub xmp: Xmp,
+    /// IPTC-IIM datasets keyed by their name like `Caption-Abstract`
+    pub iptc: Iptc,
+    /// Vendor specific fields keyed by their name like `FilmSimulation`
+    pub maker_note: BTreeMap<&'static str, String>,
+    pub text_values: TextValues,
+    /// ISO 8601 capture time to be formatted with the `date` helper
+    pub date_taken: Option<String>,
 }
 
 pub fn get_frame_data(
     (width, height): (u32, u32),
     exif: &Exif,
+    xmp: &Xmp,
+    iptc: &Iptc,
+    overrides: &Overrides,
+    config: &Config,
+    resources: &Resources,
 ) -> Result<FrameData, anyhow::Error> {
-    let text_values = get_text_values(exif);
-    let left_display_order = vec![TextValuesKeys::Camera];
-    let right_display_order = vec![
-        TextValuesKeys::Aperture,
-        TextValuesKeys::ShutterSpeed,
-        TextValuesKeys::FocalLength,
-        TextValuesKeys::Iso,
-    ];
+    let time_shift = get_time_shift(config, exif);
+    let date_taken = get_date_taken(exif, config.timezone.as_ref())
+        .and_then(|date_taken| date_taken.shift(time_shift));
+    let position = get_position(exif).or_else(|| {
+        let track = resources.track.as_ref()?;
+        get_track_position(track, date_taken.as_ref()?, config)
+    });
+    let maker_note = MakerNote::parse(exif);
+    let metadata = Metadata {
+        exif,
+        xmp,
+        iptc,
+        maker_note: maker_note.as_ref(),
+        date_taken,
+        position,
+        crop_factor: get_crop_factor(exif, config),
+        maker_note_fields: maker_note
+            .as_ref()
+            .map(|maker_note| get_maker_note_fields(maker_note, &resources.lenses))
+            .unwrap_or_default(),
+    };
+    let mut text_values = get_text_values(&metadata, config, resources);
+    overrides.apply(&mut text_values, &resources.fields, config);
+    let display_rows = get_display_orders(config, &text_values);
+    let displayed_names: Vec<&str> = display_rows
+        .iter()
+        .flat_map(DisplayOrders::iter)
+        .map(String::as_str)
+        .collect();
+    apply_missing_values(&mut text_values, &displayed_names, config)?;
     let values = get_positions(
         &text_values,
-        width,
-        &left_display_order,
-        &right_display_order,
+        &resources.fields,
+        (width, height),
+        &display_rows,
+        resources.font.as_ref(),
+        config.overflow,
     );
 
     Ok(FrameData {
         width,
-        height,
+        height: height * get_row_count(&values).max(display_rows.len() as u32),
         values,
+        exif: get_exif_values(exif, time_shift),
+        xmp: xmp.clone(),
+        iptc: iptc.clone(),
+        maker_note: metadata.maker_note_fields,
+        text_values,
+        date_taken: date_taken.map(|date_taken| date_taken.to_iso_string()),
     })
 }
 
-// TODO
-// 2. How to scale / wrap information when width is not enough
-// 3. How to display values in order rather than just fixed positions
+fn get_track_position(track: &Track, date_taken: &DateTaken, config: &Config) -> Option<Position> {
+    // GPX uses UTC, the local time without known offset is expected to be UTC as well
+    let time = match date_taken.to_date_time() {
+        Some(date_time) => date_time.with_timezone(&Utc),
+        None => date_taken.local.and_utc(),
+    }
+    .checked_add_signed(config.gpx_time_offset.0)?;
+
+    let position = track.get_position(time, config.gpx_max_gap.0);
+    debug!("Position from GPX track at {}: {:?}", time, position);
+    position
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Context};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::config::Config;
+
+use super::{
+    fields::{FieldRegistry, RawValue},
+    text_values::TextValues,
+};
+
+/// Name of the sidecar shared by the directory, e.g. the film roll, and the suffix of the image sidecars
+const SIDECAR_NAME: &str = ".metaframer";
+const SIDECAR_EXTENSIONS: [&str; 2] = ["toml", "json"];
+
+/// Values of the text fields set manually, keyed by the field name like `lens`
+///
+/// Empty value removes the extracted one so it's handled as missing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides(BTreeMap<String, String>);
+
+/// `key=value` pair from `--set`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Override {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Override {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(Override {
+                key: normalize_key(key),
+                value: value.trim().to_string(),
+            }),
+            _ => Err(format!("`{}` is not in the `key=value` format", value)),
+        }
+    }
+}
+
+/// Sidecars can store numbers like `iso = 400` as well as text
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SidecarValue {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+}
+
+impl Overrides {
+    /// Overrides from the directory sidecar, the image sidecar and `--set` in this order of precedence
+    pub fn load(path: &Path, set: &[Override]) -> Result<Overrides, anyhow::Error> {
+        let mut overrides = Overrides::default();
+        let sidecar_paths = [get_directory_sidecar_path(path), get_sidecar_path(path)];
+        for sidecar_path in sidecar_paths.into_iter().flatten() {
+            debug!("Reading overrides from {:?}", sidecar_path);
+            overrides = overrides.merge(Overrides::read(&sidecar_path)?);
+        }
+
+        let set = set
+            .iter()
+            .map(|Override { key, value }| (key.clone(), value.clone()));
+        overrides.0.extend(set);
+        Ok(overrides)
+    }
+
+    /// Reads TOML or JSON sidecar with a flat table of the field values
+    pub fn read(path: &Path) -> Result<Overrides, anyhow::Error> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("could not read sidecar `{:?}`", path))?;
+        let values: BTreeMap<String, SidecarValue> =
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => serde_json::from_str(&content)?,
+                Some("toml") => toml::from_str(&content)?,
+                _ => return Err(anyhow!("unsupported sidecar `{:?}`", path)),
+            };
+
+        Ok(Overrides(
+            values
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        SidecarValue::Text(text) => text.trim().to_string(),
+                        SidecarValue::Integer(number) => number.to_string(),
+                        SidecarValue::Float(number) => number.to_string(),
+                    };
+                    (normalize_key(&key), value)
+                })
+                .collect(),
+        ))
+    }
+
+    pub fn merge(mut self, other: Overrides) -> Overrides {
+        self.0.extend(other.0);
+        self
+    }
+
+    /// Replaces the extracted values formatted by their field, unknown fields are reported and skipped
+    pub fn apply(&self, text_values: &mut TextValues, fields: &FieldRegistry, config: &Config) {
+        for (key, value) in &self.0 {
+            match fields.get(key) {
+                Some(field) => text_values.set(
+                    key,
+                    Some(value)
+                        .filter(|value| !value.is_empty())
+                        .and_then(|value| (field.formatter)(&RawValue::Text(value.clone()), config))
+                        .filter(|value| !value.is_empty()),
+                ),
+                None => warn!("Unknown field `{}` can't be overridden", key),
+            }
+        }
+    }
+}
+
+/// `lens`, `Lens` and `date-taken` are accepted for the `date_taken` style field names
+pub(crate) fn normalize_key(key: &str) -> String {
+    key.trim().to_lowercase().replace('-', "_")
+}
+
+/// `.metaframer.toml` or `.metaframer.json` next to the image
+fn get_directory_sidecar_path(path: &Path) -> Option<PathBuf> {
+    SIDECAR_EXTENSIONS
+        .iter()
+        .map(|extension| path.with_file_name(format!("{}.{}", SIDECAR_NAME, extension)))
+        .find(|sidecar_path| sidecar_path.is_file())
+}
+
+/// Sidecar named either `IMG_1234.metaframer.toml` or `IMG_1234.jpg.metaframer.toml`
+fn get_sidecar_path(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let file_stem = path.file_stem()?.to_str()?;
+    [file_stem, file_name]
+        .iter()
+        .flat_map(|name| {
+            SIDECAR_EXTENSIONS
+                .iter()
+                .map(move |extension| format!("{}{}.{}", name, SIDECAR_NAME, extension))
+        })
+        .map(|sidecar_name| path.with_file_name(sidecar_name))
+        .find(|sidecar_path| sidecar_path.is_file())
+}
+
+#[test]
+fn test_apply_overrides() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("scan.jpg");
+    fs::write(
+        dir.path().join(".metaframer.toml"),
+        "camera = \"Pentax 67\"\nlens = \"SMC Takumar 105mm f/2.4\"\niso = 400",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("scan.metaframer.json"),
+        r#"{ "lens": "SMC Pentax 67 55mm f/4", "date-taken": "" }"#,
+    )
+    .unwrap();
+
+    let set = [
+        "iso=800".parse::<Override>().unwrap(),
+        "aperture=2.8".parse().unwrap(),
+        "shutter-speed=0.004".parse().unwrap(),
+    ];
+    let overrides = Overrides::load(&path, &set).unwrap();
+    let mut text_values = TextValues::from([
+        ("camera", "Nikon Super Coolscan 5000 ED"),
+        ("date_taken", "2024-07-14 18:05"),
+    ]);
+    let config = Config::default();
+    let fields = FieldRegistry::load(&config);
+    overrides.apply(&mut text_values, &fields, &config);
+
+    assert_eq!(text_values.get("camera").unwrap(), "Pentax 67");
+    assert_eq!(text_values.get("lens").unwrap(), "SMC Pentax 67 55mm f/4");
+    assert_eq!(text_values.get("iso").unwrap(), "800");
+    assert_eq!(text_values.get("aperture").unwrap(), "f/2.8");
+    assert_eq!(text_values.get("shutter_speed").unwrap(), "1/250s");
+    assert_eq!(text_values.get("date_taken"), None);
+    assert!("lens".parse::<Override>().is_err());
+}
@@ -1,104 +1,409 @@
-use serde::Serialize;
+use clap::ValueEnum;
+use log::debug;
+use serde::{Deserialize, Serialize};
 
 use super::{
-    text_values::{TextValues, TextValuesKeys},
+    fields::FieldRegistry, font::Font, layout::DisplayOrders, text_values::TextValues,
     FrameSettings,
 };
 
-#[derive(Serialize, Debug, PartialEq, Clone)]
+/// Handling of the values that don't fit into the width of the frame
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overflow {
+    /// Scales the text and the icons down, up to the half of their size
+    #[default]
+    Shrink,
+    /// Shortens the values from the end of the groups with an ellipsis
+    Truncate,
+    /// Leaves out the values from the end of the groups
+    Drop,
+    /// Moves the right group to the next row
+    Wrap,
+}
+
+const MIN_SCALE: f64 = 0.5;
+const SCALE_STEP: f64 = 0.05;
+/// Letters kept in front of the ellipsis of a truncated value
+const MIN_TRUNCATED_LENGTH: usize = 3;
+
+#[derive(Serialize, Debug, Default, PartialEq, Clone)]
 pub struct PositionedValue {
     text_position: i32,
     icon_position: i32,
     text: String,
-    value_key: TextValuesKeys,
+    /// Name of the field, the icon partial is registered under it as well
+    value_key: String,
+    /// Index of the row, the rows are the frame height apart
+    row: u32,
+    /// Baseline of the text
+    text_y: i32,
+    /// Top edge of the icon
+    icon_y: i32,
+    /// Size of the font in pixels, smaller than the template's one when the values are scaled down
+    font_size: f64,
+    icon_size: i32,
 }
 
+/// Left, center and right group of a single row
+type Row = [Vec<PositionedValue>; 3];
+
 pub fn get_positions(
     text_values: &TextValues,
-    width: u32,
-    left_display_order: &[TextValuesKeys],
-    right_display_order: &[TextValuesKeys],
+    fields: &FieldRegistry,
+    (width, row_height): (u32, u32),
+    display_rows: &[DisplayOrders],
+    font: Option<&Font>,
+    overflow: Overflow,
 ) -> Vec<PositionedValue> {
-    let frame_settings = FrameSettings {
+    let initial_settings = FrameSettings {
         inner_border: 5,
         outer_border: 10,
         icon_size: 30,
         letter_width: 12,
+        font,
+        scale: 1.,
+        row_height: row_height as i32,
+    };
+    let mut frame_settings = initial_settings;
+    let layout = |frame_settings: &FrameSettings| -> Vec<Row> {
+        display_rows
+            .iter()
+            .map(|display_orders| {
+                get_row_positions(frame_settings, text_values, fields, display_orders, width)
+            })
+            .collect()
+    };
+    let overflows = |frame_settings: &FrameSettings, rows: &[Row]| {
+        rows.iter()
+            .any(|row| has_collision(frame_settings, row, width))
     };
 
-    let left_positions =
-        get_left_aligned_positions(&frame_settings, text_values, left_display_order);
+    let mut rows = layout(&frame_settings);
+    if overflows(&frame_settings, &rows) {
+        match overflow {
+            // All the rows are scaled the same so their text has the same size
+            Overflow::Shrink => {
+                while frame_settings.scale > MIN_SCALE && overflows(&frame_settings, &rows) {
+                    // Rounded so the font size doesn't carry the error of the repeated steps
+                    let scale = ((frame_settings.scale - SCALE_STEP) * 100.).round() / 100.;
+                    let scale = scale.max(MIN_SCALE);
+                    frame_settings = initial_settings.get_scaled(scale);
+                    rows = layout(&frame_settings);
+                }
+                debug!(
+                    "Values overflow the frame, the text and icons are scaled down to {:.0}%",
+                    frame_settings.scale * 100.
+                );
+            }
+            _ => {
+                rows = display_rows
+                    .iter()
+                    .flat_map(|display_orders| {
+                        fit_row(
+                            &frame_settings,
+                            text_values,
+                            fields,
+                            display_orders,
+                            width,
+                            overflow,
+                        )
+                    })
+                    .collect();
+            }
+        }
+        if overflows(&frame_settings, &rows) {
+            debug!("Values still overflow the frame");
+        }
+    }
+
+    let frame_settings = &frame_settings;
+    rows.into_iter()
+        .enumerate()
+        .flat_map(|(index, row)| {
+            row.concat()
+                .into_iter()
+                .map(move |value| place_in_row(frame_settings, value, index as u32))
+        })
+        .collect()
+}
+
+/// Truncates, drops or wraps the values of the row that doesn't fit into the frame
+fn fit_row(
+    frame_settings: &FrameSettings,
+    text_values: &TextValues,
+    fields: &FieldRegistry,
+    display_orders: &DisplayOrders,
+    width: u32,
+    overflow: Overflow,
+) -> Vec<Row> {
+    let layout = |text_values: &TextValues, display_orders: &DisplayOrders| {
+        get_row_positions(frame_settings, text_values, fields, display_orders, width)
+    };
+    let mut text_values = text_values.clone();
+    let mut display_orders = display_orders.clone();
+    let mut row = layout(&text_values, &display_orders);
+    if !has_collision(frame_settings, &row, width) {
+        return vec![row];
+    }
+    let has_right_values = display_orders
+        .right
+        .iter()
+        .any(|name| text_values.get(name).is_some());
+    let overflow = match overflow {
+        // Wrapping without any value in the right group would add an empty row
+        Overflow::Wrap if !has_right_values => Overflow::Drop,
+        overflow => overflow,
+    };
 
-    let right_positions =
-        get_right_aligned_positions(&frame_settings, text_values, right_display_order, width);
+    match overflow {
+        Overflow::Truncate => {
+            for name in get_drop_order(&display_orders, &text_values) {
+                let Some(text) = text_values.get(&name).cloned() else {
+                    continue;
+                };
+                let mut letters: Vec<char> = text.chars().collect();
+                while letters.len() > MIN_TRUNCATED_LENGTH
+                    && has_collision(frame_settings, &row, width)
+                {
+                    letters.pop();
+                    let truncated = letters.iter().collect::<String>();
+                    text_values.set(&name, Some(format!("{}…", truncated.trim_end())));
+                    row = layout(&text_values, &display_orders);
+                }
+                if text_values.get(&name) != Some(&text) {
+                    debug!(
+                        "Values overflow the frame, `{}` is truncated to `{}`",
+                        name,
+                        text_values.get(&name).unwrap()
+                    );
+                }
+                if !has_collision(frame_settings, &row, width) {
+                    break;
+                }
+            }
+            vec![row]
+        }
+        Overflow::Drop => {
+            for name in get_drop_order(&display_orders, &text_values) {
+                if !has_collision(frame_settings, &row, width) {
+                    break;
+                }
+                display_orders.remove(&name);
+                row = layout(&text_values, &display_orders);
+                debug!("Values overflow the frame, `{}` is dropped", name);
+            }
+            vec![row]
+        }
+        Overflow::Wrap => {
+            let wrapped_orders = DisplayOrders {
+                right: std::mem::take(&mut display_orders.right),
+                ..DisplayOrders::default()
+            };
+            debug!("Values overflow the frame, the right group is wrapped to the next row");
+            vec![
+                layout(&text_values, &display_orders),
+                layout(&text_values, &wrapped_orders),
+            ]
+        }
+        Overflow::Shrink => vec![row],
+    }
+}
 
-    [left_positions, right_positions].concat()
+/// Number of rows the values are placed in
+pub fn get_row_count(positioned_values: &[PositionedValue]) -> u32 {
+    positioned_values
+        .iter()
+        .map(|value| value.row + 1)
+        .max()
+        .unwrap_or(1)
+}
+
+fn get_row_positions(
+    frame_settings: &FrameSettings,
+    text_values: &TextValues,
+    fields: &FieldRegistry,
+    display_orders: &DisplayOrders,
+    width: u32,
+) -> Row {
+    let left_positions = get_left_aligned_positions(
+        frame_settings,
+        text_values,
+        fields,
+        &as_str(&display_orders.left),
+    );
+
+    let right_positions = get_right_aligned_positions(
+        frame_settings,
+        text_values,
+        fields,
+        &as_str(&display_orders.right),
+        width,
+    );
+
+    let center_positions = get_centered_positions(
+        frame_settings,
+        text_values,
+        fields,
+        &as_str(&display_orders.center),
+        width,
+    );
+
+    [left_positions, center_positions, right_positions]
+}
+
+/// Groups running into each other or over the edges of the frame
+fn has_collision(frame_settings: &FrameSettings, row: &Row, width: u32) -> bool {
+    let spans: Vec<(i32, i32)> = row
+        .iter()
+        .filter_map(|group| {
+            let first_value = group.first()?;
+            let last_value = group.last()?;
+            let end = last_value.text_position + frame_settings.get_text_width(&last_value.text);
+            Some((first_value.icon_position, end))
+        })
+        .collect();
+
+    let overlaps = spans
+        .windows(2)
+        .any(|pair| pair[0].1 + frame_settings.outer_border > pair[1].0);
+    let outside = spans.first().is_some_and(|span| span.0 < 0)
+        || spans.last().is_some_and(|span| span.1 > width as i32);
+    overlaps || outside
+}
+
+/// Displayed values from the least important one, the values at the end of the groups go first
+fn get_drop_order(display_orders: &DisplayOrders, text_values: &TextValues) -> Vec<String> {
+    let groups: Vec<Vec<&String>> = [
+        &display_orders.right,
+        &display_orders.left,
+        &display_orders.center,
+    ]
+    .into_iter()
+    .map(|display_order| {
+        display_order
+            .iter()
+            .filter(|name| text_values.get(name).is_some())
+            .rev()
+            .collect()
+    })
+    .collect();
+    let longest_group = groups.iter().map(Vec::len).max().unwrap_or(0);
+
+    (0..longest_group)
+        .flat_map(|index| groups.iter().filter_map(move |group| group.get(index)))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Sets the vertical position of the value, the icon and the text are centered in the row
+fn place_in_row(
+    frame_settings: &FrameSettings,
+    value: PositionedValue,
+    row: u32,
+) -> PositionedValue {
+    let row_top = row as i32 * frame_settings.row_height;
+    let font_size = frame_settings.get_font_size();
+    PositionedValue {
+        row,
+        icon_y: row_top + (frame_settings.row_height - frame_settings.icon_size) / 2,
+        // Baseline of the capital letters centered in the row
+        text_y: row_top + frame_settings.row_height / 2 + (font_size * 0.35).round() as i32,
+        font_size,
+        icon_size: frame_settings.icon_size,
+        ..value
+    }
 }
 
 fn get_left_aligned_positions(
-    FrameSettings {
-        letter_width,
-        inner_border,
-        outer_border,
-        icon_size,
-    }: &FrameSettings,
+    frame_settings: &FrameSettings,
     text_values: &TextValues,
-    display_order: &[TextValuesKeys],
+    fields: &FieldRegistry,
+    display_order: &[&str],
 ) -> Vec<PositionedValue> {
     let mut positioned_values: Vec<PositionedValue> = vec![];
 
-    for prop in display_order {
-        let (icon_position, text_position) = match positioned_values.last() {
+    for name in display_order {
+        // Hidden missing values are left out together with their icon
+        let Some(text) = text_values.get(name) else {
+            continue;
+        };
+        let icon_space = get_icon_space(frame_settings, fields, name);
+        let icon_position = match positioned_values.last() {
             Some(last_value) => {
-                let last_text_len = last_value.text.len() as i32;
-                let next_icon_position =
-                    last_value.text_position + last_text_len * letter_width + outer_border;
-                let next_text_position = next_icon_position + icon_size + inner_border;
-                (next_icon_position, next_text_position)
+                last_value.text_position
+                    + frame_settings.get_text_width(&last_value.text)
+                    + frame_settings.outer_border
             }
-            None => (*outer_border, outer_border + icon_size + inner_border),
+            None => frame_settings.outer_border,
         };
 
         positioned_values.push(PositionedValue {
-            text_position,
+            text_position: icon_position + icon_space,
             icon_position,
-            text: text_values.get_property(prop).clone(),
-            value_key: *prop,
+            text: text.clone(),
+            value_key: name.to_string(),
+            ..PositionedValue::default()
         })
     }
 
     positioned_values
 }
 
+/// Lays out the values like the left aligned ones and moves them to the center of the frame
+fn get_centered_positions(
+    frame_settings: &FrameSettings,
+    text_values: &TextValues,
+    fields: &FieldRegistry,
+    display_order: &[&str],
+    width: u32,
+) -> Vec<PositionedValue> {
+    let mut positioned_values =
+        get_left_aligned_positions(frame_settings, text_values, fields, display_order);
+    let Some(last_value) = positioned_values.last() else {
+        return positioned_values;
+    };
+
+    let group_width = last_value.text_position
+        + frame_settings.get_text_width(&last_value.text)
+        + frame_settings.outer_border;
+    let offset = (width as i32 - group_width) / 2;
+    positioned_values.iter_mut().for_each(|value| {
+        value.text_position += offset;
+        value.icon_position += offset;
+    });
+
+    positioned_values
+}
+
 fn get_right_aligned_positions(
-    FrameSettings {
-        letter_width,
-        inner_border,
-        outer_border,
-        icon_size,
-    }: &FrameSettings,
+    frame_settings: &FrameSettings,
     text_values: &TextValues,
-    display_order: &[TextValuesKeys],
+    fields: &FieldRegistry,
+    display_order: &[&str],
     width: u32,
 ) -> Vec<PositionedValue> {
     let mut positioned_values: Vec<PositionedValue> = vec![];
     let reversed_order = display_order.iter().rev();
 
-    for prop in reversed_order {
+    for name in reversed_order {
         let last_icon_position = positioned_values
             .last()
             .map_or(&0, |value| &value.icon_position);
 
-        let text = text_values.get_property(prop).clone();
-        let text_size = text.len();
-        let text_position = last_icon_position + outer_border + text_size as i32 * letter_width;
-        let icon_position = text_position + inner_border + icon_size;
+        let Some(text) = text_values.get(name).cloned() else {
+            continue;
+        };
+        let text_position =
+            last_icon_position + frame_settings.outer_border + frame_settings.get_text_width(&text);
+        let icon_position = text_position + get_icon_space(frame_settings, fields, name);
 
         positioned_values.push(PositionedValue {
             text_position,
             icon_position,
             text,
-            value_key: *prop,
+            value_key: name.to_string(),
+            ..PositionedValue::default()
         })
     }
 
@@ -111,6 +416,18 @@ fn get_right_aligned_positions(
     positioned_values
 }
 
+fn as_str(names: &[String]) -> Vec<&str> {
+    names.iter().map(String::as_str).collect()
+}
+
+/// Width of the icon and the gap after it, fields without an icon don't take any space
+fn get_icon_space(frame_settings: &FrameSettings, fields: &FieldRegistry, name: &str) -> i32 {
+    match fields.has_icon(name) {
+        true => frame_settings.icon_size + frame_settings.inner_border,
+        false => 0,
+    }
+}
+
 #[test]
 fn test_get_left_aligned_positions() {
     let frame_settings = FrameSettings {
@@ -118,29 +435,28 @@ fn test_get_left_aligned_positions() {
         outer_border: 10,
         icon_size: 30,
         letter_width: 10,
+        font: None,
+        scale: 1.,
+        row_height: 40,
     };
+    let fields = FieldRegistry::load(&crate::config::Config::default());
     let FrameSettings {
         letter_width,
         inner_border,
         outer_border,
         icon_size,
+        ..
     } = frame_settings;
 
-    let text_values = TextValues {
-        camera: "My camera 1234".to_string(),
-        aperture: "f/8".to_string(),
-        shutter_speed: "1/250s".to_string(),
-        focal_length: "18.1mm".to_string(),
-        iso: "3600".to_string(),
-    };
+    let text_values = TextValues::from([
+        ("camera", "My camera 1234"),
+        ("aperture", "f/8"),
+        ("shutter_speed", "1/250s"),
+        ("focal_length", "18.1mm"),
+        ("iso", "3600"),
+    ]);
 
-    let display_order = vec![
-        TextValuesKeys::Camera,
-        TextValuesKeys::Aperture,
-        TextValuesKeys::ShutterSpeed,
-        TextValuesKeys::FocalLength,
-        TextValuesKeys::Iso,
-    ];
+    let display_order = vec!["camera", "aperture", "shutter_speed", "focal_length", "iso"];
 
     // 14 * 10 + borders
     let first_value = outer_border + icon_size + inner_border;
@@ -157,37 +473,42 @@ fn test_get_left_aligned_positions() {
         PositionedValue {
             text_position: first_value,
             icon_position: 10,
-            text: text_values.camera.clone(),
-            value_key: TextValuesKeys::Camera,
+            text: text_values.get("camera").unwrap().clone(),
+            value_key: "camera".to_string(),
+            ..PositionedValue::default()
         },
         PositionedValue {
             text_position: second_value,
             icon_position: second_icon,
-            text: text_values.aperture.clone(),
-            value_key: TextValuesKeys::Aperture,
+            text: text_values.get("aperture").unwrap().clone(),
+            value_key: "aperture".to_string(),
+            ..PositionedValue::default()
         },
         PositionedValue {
             text_position: third_value,
             icon_position: third_icon,
-            text: text_values.shutter_speed.clone(),
-            value_key: TextValuesKeys::ShutterSpeed,
+            text: text_values.get("shutter_speed").unwrap().clone(),
+            value_key: "shutter_speed".to_string(),
+            ..PositionedValue::default()
         },
         PositionedValue {
             text_position: fourth_value,
             icon_position: fourth_icon,
-            text: text_values.focal_length.clone(),
-            value_key: TextValuesKeys::FocalLength,
+            text: text_values.get("focal_length").unwrap().clone(),
+            value_key: "focal_length".to_string(),
+            ..PositionedValue::default()
         },
         PositionedValue {
             text_position: fifth_value,
             icon_position: fifth_icon,
-            text: text_values.iso.clone(),
-            value_key: TextValuesKeys::Iso,
+            text: text_values.get("iso").unwrap().clone(),
+            value_key: "iso".to_string(),
+            ..PositionedValue::default()
         },
     ];
 
     assert_eq!(
-        get_left_aligned_positions(&frame_settings, &text_values, &display_order),
+        get_left_aligned_positions(&frame_settings, &text_values, &fields, &display_order),
         expected_positions
     );
 }
@@ -199,28 +520,27 @@ fn test_get_right_aligned_positions() {
         outer_border: 10,
         icon_size: 30,
         letter_width: 10,
+        font: None,
+        scale: 1.,
+        row_height: 40,
     };
+    let fields = FieldRegistry::load(&crate::config::Config::default());
     let FrameSettings {
         letter_width,
         inner_border,
         outer_border,
         icon_size,
+        ..
     } = frame_settings;
 
-    let text_values = TextValues {
-        camera: "My camera 1234".to_string(),
-        aperture: "f/8".to_string(),
-        shutter_speed: "1/250s".to_string(),
-        focal_length: "18.1mm".to_string(),
-        iso: "3600".to_string(),
-    };
+    let text_values = TextValues::from([
+        ("aperture", "f/8"),
+        ("shutter_speed", "1/250s"),
+        ("focal_length", "18.1mm"),
+        ("iso", "3600"),
+    ]);
 
-    let display_order = vec![
-        TextValuesKeys::Aperture,
-        TextValuesKeys::ShutterSpeed,
-        TextValuesKeys::FocalLength,
-        TextValuesKeys::Iso,
-    ];
+    let display_order = vec!["aperture", "shutter_speed", "focal_length", "iso"];
     let width = 1000;
 
     let first_value = outer_border + letter_width * 4;
@@ -247,31 +567,227 @@ fn test_get_right_aligned_positions() {
         PositionedValue {
             text_position: values[0],
             icon_position: icons[0],
-            text: text_values.aperture.clone(),
-            value_key: TextValuesKeys::Aperture,
+            text: text_values.get("aperture").unwrap().clone(),
+            value_key: "aperture".to_string(),
+            ..PositionedValue::default()
         },
         PositionedValue {
             text_position: values[1],
             icon_position: icons[1],
-            text: text_values.shutter_speed.clone(),
-            value_key: TextValuesKeys::ShutterSpeed,
+            text: text_values.get("shutter_speed").unwrap().clone(),
+            value_key: "shutter_speed".to_string(),
+            ..PositionedValue::default()
         },
         PositionedValue {
             text_position: values[2],
             icon_position: icons[2],
-            text: text_values.focal_length.clone(),
-            value_key: TextValuesKeys::FocalLength,
+            text: text_values.get("focal_length").unwrap().clone(),
+            value_key: "focal_length".to_string(),
+            ..PositionedValue::default()
         },
         PositionedValue {
             text_position: values[3],
             icon_position: icons[3],
-            text: text_values.iso.clone(),
-            value_key: TextValuesKeys::Iso,
+            text: text_values.get("iso").unwrap().clone(),
+            value_key: "iso".to_string(),
+            ..PositionedValue::default()
         },
     ];
 
     assert_eq!(
-        get_right_aligned_positions(&frame_settings, &text_values, &display_order, width as u32),
+        get_right_aligned_positions(
+            &frame_settings,
+            &text_values,
+            &fields,
+            &display_order,
+            width as u32
+        ),
         expected_positions
     );
 }
+
+#[test]
+fn test_get_centered_positions() {
+    let frame_settings = FrameSettings {
+        inner_border: 5,
+        outer_border: 10,
+        icon_size: 30,
+        letter_width: 10,
+        font: None,
+        scale: 1.,
+        row_height: 40,
+    };
+    let fields = FieldRegistry::load(&crate::config::Config::default());
+    let text_values = TextValues::from([("signature", "Jane Doe")]);
+
+    // 10 + 30 + 5 + 8 * 10 + 10 = 135 wide group in the middle of 1000
+    let offset = (1000 - 135) / 2;
+    assert_eq!(
+        get_centered_positions(&frame_settings, &text_values, &fields, &["signature"], 1000),
+        vec![PositionedValue {
+            text_position: offset + 45,
+            icon_position: offset + 10,
+            text: "Jane Doe".to_string(),
+            value_key: "signature".to_string(),
+            ..PositionedValue::default()
+        }]
+    );
+}
+
+#[test]
+fn test_get_positions_skips_hidden_values() {
+    let text_values = TextValues::from([
+        ("camera", "X-T30"),
+        ("title", "Dusk"),
+        ("aperture", "f/8"),
+        ("iso", "400"),
+    ]);
+    let fields = FieldRegistry::load(&crate::config::Config::default());
+
+    let positions = get_positions(
+        &text_values,
+        &fields,
+        (1000, 40),
+        &[DisplayOrders {
+            left: ["lens", "camera", "title"].map(String::from).to_vec(),
+            center: vec!["signature".to_string()],
+            right: ["aperture", "shutter_speed", "iso"]
+                .map(String::from)
+                .to_vec(),
+        }],
+        None,
+        Overflow::Shrink,
+    );
+
+    let keys: Vec<&str> = positions
+        .iter()
+        .map(|value| value.value_key.as_str())
+        .collect();
+    assert_eq!(keys, vec!["camera", "title", "aperture", "iso"]);
+    assert_eq!(positions[0].icon_position, 10);
+    // The title without an icon starts right after the gap
+    assert_eq!(positions[1].icon_position, positions[1].text_position);
+}
+
+#[test]
+fn test_get_positions_handles_overflow() {
+    let text_values = TextValues::from([
+        ("camera", "Fujifilm X-T30"),
+        ("lens", "XF23mmF2 R WR"),
+        ("aperture", "f/2.8"),
+        ("shutter_speed", "1/250s"),
+        ("iso", "400"),
+    ]);
+    let fields = FieldRegistry::load(&crate::config::Config::default());
+    let display_rows = vec![DisplayOrders {
+        left: ["camera", "lens"].map(String::from).to_vec(),
+        center: vec![],
+        right: ["aperture", "shutter_speed", "iso"]
+            .map(String::from)
+            .to_vec(),
+    }];
+    let get_overflowing_positions = |overflow| {
+        get_positions(
+            &text_values,
+            &fields,
+            (500, 40),
+            &display_rows,
+            None,
+            overflow,
+        )
+    };
+
+    let positions = get_overflowing_positions(Overflow::Shrink);
+    assert!(positions[0].font_size < 18.);
+    assert!(positions[0].icon_size < 30);
+    assert_eq!(get_row_count(&positions), 1);
+
+    let positions = get_overflowing_positions(Overflow::Truncate);
+    assert_eq!(positions.len(), 5);
+    assert!(positions.iter().any(|value| value.text.ends_with('…')));
+    assert_eq!(positions[0].font_size, 18.);
+
+    let positions = get_overflowing_positions(Overflow::Drop);
+    let keys: Vec<&str> = positions
+        .iter()
+        .map(|value| value.value_key.as_str())
+        .collect();
+    assert_eq!(keys, vec!["camera", "aperture", "shutter_speed"]);
+
+    let positions = get_overflowing_positions(Overflow::Wrap);
+    assert_eq!(get_row_count(&positions), 2);
+    assert_eq!(positions[0].row, 0);
+    assert_eq!(positions[2].row, 1);
+    assert_eq!(positions[2].text_y, positions[0].text_y + 40);
+    assert_eq!(positions[2].icon_y, positions[0].icon_y + 40);
+
+    // Values without the right group are dropped instead of wrapped
+    let display_rows = vec![DisplayOrders {
+        left: ["camera", "lens", "aperture", "shutter_speed", "iso"]
+            .map(String::from)
+            .to_vec(),
+        ..DisplayOrders::default()
+    }];
+    let positions = get_positions(
+        &text_values,
+        &fields,
+        (500, 40),
+        &display_rows,
+        None,
+        Overflow::Wrap,
+    );
+    assert_eq!(get_row_count(&positions), 1);
+    assert!(positions.len() < 5);
+}
+
+#[test]
+fn test_get_positions_in_rows() {
+    let text_values = TextValues::from([
+        ("camera", "X-T30"),
+        ("lens", "XF23mmF2 R WR"),
+        ("aperture", "f/2.8"),
+        ("iso", "400"),
+    ]);
+    let fields = FieldRegistry::load(&crate::config::Config::default());
+    let display_rows = vec![
+        DisplayOrders {
+            left: ["camera", "lens"].map(String::from).to_vec(),
+            ..DisplayOrders::default()
+        },
+        DisplayOrders {
+            right: ["aperture", "iso"].map(String::from).to_vec(),
+            ..DisplayOrders::default()
+        },
+    ];
+
+    let positions = get_positions(
+        &text_values,
+        &fields,
+        (1000, 40),
+        &display_rows,
+        None,
+        Overflow::Shrink,
+    );
+
+    let rows: Vec<(&str, u32, i32, i32)> = positions
+        .iter()
+        .map(|value| {
+            (
+                value.value_key.as_str(),
+                value.row,
+                value.icon_y,
+                value.text_y,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("camera", 0, 5, 26),
+            ("lens", 0, 5, 26),
+            ("aperture", 1, 45, 66),
+            ("iso", 1, 45, 66),
+        ]
+    );
+    assert_eq!(get_row_count(&positions), 2);
+}
@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+use crate::config::Config;
+
+use super::camera::get_camera_name;
+
+/// Camera, lens and film of the scanned film shots whose EXIF describes the scanner
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FilmProfile {
+    pub name: String,
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    pub film_stock: Option<String>,
+    /// Box speed of the film or the speed it was pushed/pulled to
+    pub iso: Option<u32>,
+    /// Focal length of the lens in millimeters
+    pub focal_length: Option<f64>,
+    /// Crop factor of the film format, looked up in `camera-crop-factor` by the camera without it
+    pub crop_factor: Option<f64>,
+}
+
+/// Profile selected with `--profile` or `profile` in the config file
+pub fn get_profile(config: &Config) -> Option<&FilmProfile> {
+    let name = config.profile.as_ref()?;
+    config
+        .film_profiles
+        .iter()
+        .find(|profile| profile.name.trim().eq_ignore_ascii_case(name.trim()))
+}
+
+/// Crop factor of the profile set directly or configured for the camera named like the profile camera
+pub fn get_profile_crop_factor(profile: &FilmProfile, config: &Config) -> Option<f64> {
+    profile.crop_factor.or_else(|| {
+        let camera = profile.camera.as_ref()?;
+        config
+            .camera_crop_factors
+            .iter()
+            .find(|entry| {
+                get_camera_name(&entry.make, &entry.model, &config.camera_aliases)
+                    .eq_ignore_ascii_case(camera.trim())
+            })
+            .map(|entry| entry.crop_factor)
+    })
+}
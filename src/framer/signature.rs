@@ -0,0 +1,92 @@
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+/// Used when the signature isn't configured and the copyright notice is missing
+pub const DEFAULT_SIGNATURE: &str = "© {year} {artist}";
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// Values available to the signature placeholders
+pub struct SignatureValues<'a> {
+    pub artist: Option<&'a str>,
+    pub copyright: Option<&'a str>,
+    pub year: Option<i32>,
+}
+
+/// Fills `{artist}`, `{copyright}` and `{year}` placeholders of the signature
+///
+/// Without the configured signature the copyright notice is used, falling back to
+/// `© {year} {artist}`. `None` is returned when the artist or copyright used by the signature is missing,
+/// the missing year is left out.
+pub fn get_signature(signature: Option<&str>, values: &SignatureValues) -> Option<String> {
+    let signature = match (signature, values.copyright) {
+        (Some(signature), _) => signature,
+        (None, Some(copyright)) => return Some(format_copyright(copyright)),
+        (None, None) => DEFAULT_SIGNATURE,
+    };
+
+    let mut is_complete = true;
+    let filled = PLACEHOLDER.replace_all(signature, |captures: &Captures| {
+        let value = match &captures[1] {
+            "artist" => values.artist.map(str::to_string),
+            "copyright" => values.copyright.map(str::to_string),
+            "year" => Some(values.year.map(|year| year.to_string()).unwrap_or_default()),
+            _ => Some(captures[0].to_string()),
+        };
+        value.unwrap_or_else(|| {
+            is_complete = false;
+            String::new()
+        })
+    });
+
+    let filled = filled.split_whitespace().collect::<Vec<&str>>().join(" ");
+    Some(filled).filter(|filled| is_complete && !filled.is_empty())
+}
+
+/// Adds the copyright sign unless the notice already contains it
+fn format_copyright(copyright: &str) -> String {
+    let lowercase = copyright.to_lowercase();
+    if copyright.contains('©') || lowercase.starts_with("(c)") || lowercase.starts_with("copyright")
+    {
+        copyright.to_string()
+    } else {
+        format!("© {}", copyright)
+    }
+}
+
+#[test]
+fn test_get_signature() {
+    let values = SignatureValues {
+        artist: Some("Jane Doe"),
+        copyright: None,
+        year: Some(2024),
+    };
+    assert_eq!(
+        get_signature(None, &values),
+        Some("© 2024 Jane Doe".to_string())
+    );
+    assert_eq!(
+        get_signature(Some("Photo: {artist}, {year}"), &values),
+        Some("Photo: Jane Doe, 2024".to_string())
+    );
+    assert_eq!(get_signature(Some("{copyright}"), &values), None);
+
+    let values = SignatureValues {
+        artist: Some("Jane Doe"),
+        copyright: Some("© Jane Doe, all rights reserved"),
+        year: None,
+    };
+    assert_eq!(
+        get_signature(None, &values),
+        Some("© Jane Doe, all rights reserved".to_string())
+    );
+    assert_eq!(format_copyright("Jane Doe"), "© Jane Doe");
+    assert_eq!(
+        format_copyright("Copyright 2024 Jane Doe"),
+        "Copyright 2024 Jane Doe"
+    );
+    assert_eq!(
+        get_signature(Some("© {year} {artist}"), &values),
+        Some("© Jane Doe".to_string())
+    );
+}
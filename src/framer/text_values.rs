@@ -1,103 +1,401 @@
-use exif::{Exif, In, Tag};
-use regex::Regex;
+use chrono::Datelike;
+use exif::{Exif, In, Tag, Value};
 use serde::Serialize;
+use std::collections::BTreeMap;
 
-#[derive(Serialize)]
-pub struct TextValues {
-    pub shutter_speed: String,
-    pub aperture: String,
-    pub focal_length: String,
-    pub iso: String,
-    pub camera: String,
-    // pub lens: String,
-}
+use crate::config::Config;
 
-#[derive(Copy, Clone, Serialize, Debug, PartialEq, Eq)]
-pub enum TextValuesKeys {
-    Camera,
-    Aperture,
-    ShutterSpeed,
-    FocalLength,
-    Iso,
-}
+use super::{
+    camera::get_camera_name,
+    date_time::format_date_taken,
+    exposure::{
+        get_exposure_bias, get_exposure_program, get_flash, get_metering_mode, get_white_balance,
+    },
+    fields::Field,
+    focal_length::{format_focal_length, get_equivalent_aperture, get_focal_lengths},
+    formatter::{format_aperture, format_iso, format_shutter_speed},
+    gps::{
+        format_altitude, format_direction, format_latitude, format_longitude, get_image_direction,
+    },
+    iso::get_iso,
+    lens_database::LensDatabase,
+    makernote::{self, MakerNote},
+    metadata::Metadata,
+    profile::get_profile,
+    signature::{get_signature, SignatureValues},
+    xmp::Xmp,
+    Resources,
+};
+
+/// Formatted values of the registered fields keyed by their name, missing values are left out
+#[derive(Serialize, Clone, Default, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct TextValues(BTreeMap<String, String>);
 
 impl TextValues {
-    // Function to get a property by its key
-    pub fn get_property(&self, key: &TextValuesKeys) -> &String {
-        match key {
-            TextValuesKeys::Camera => &self.camera,
-            TextValuesKeys::ShutterSpeed => &self.shutter_speed,
-            TextValuesKeys::Aperture => &self.aperture,
-            TextValuesKeys::FocalLength => &self.focal_length,
-            TextValuesKeys::Iso => &self.iso,
-        }
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.0.get(name)
+    }
+
+    /// `None` removes the value
+    pub fn set(&mut self, name: &str, value: Option<String>) {
+        match value {
+            Some(value) => self.0.insert(name.to_string(), value),
+            None => self.0.remove(name),
+        };
     }
 }
 
-pub fn get_text_values(exif: &Exif) -> TextValues {
-    TextValues {
-        shutter_speed: get_shutter_speed(exif),
-        aperture: get_aperture(exif),
-        focal_length: get_focal_length(exif),
-        camera: get_camera(exif),
-        iso: get_iso(exif),
+impl<const N: usize> From<[(&str, &str); N]> for TextValues {
+    fn from(values: [(&str, &str); N]) -> Self {
+        TextValues(
+            values
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
     }
 }
 
-pub fn get_shutter_speed(exif: &Exif) -> String {
-    let field = exif.get_field(Tag::ExposureTime, In::PRIMARY);
-    let regexp = Regex::new(r"\.\d+").unwrap();
-    match field {
-        Some(value) => {
-            let formatted = format!("{}", value.display_value().with_unit(exif)).replace(" ", "");
-            regexp.replace(&formatted, "").to_string()
+/// Exposure of the scan that doesn't describe the film shot, left out with a film profile
+const SCANNER_VALUES: [&str; 8] = [
+    "aperture",
+    "equivalent_aperture",
+    "shutter_speed",
+    "exposure_bias",
+    "exposure_program",
+    "metering_mode",
+    "flash",
+    "white_balance",
+];
+
+pub fn get_text_values(metadata: &Metadata, config: &Config, resources: &Resources) -> TextValues {
+    let mut text_values = TextValues::default();
+    let has_profile = get_profile(config).is_some();
+    for field in resources.fields.iter() {
+        if has_profile && SCANNER_VALUES.contains(&field.name.as_str()) {
+            continue;
         }
-        None => "N/A".to_string(),
+        let value = field.get_value(metadata, config, resources);
+        text_values.set(&field.name, value);
     }
+    text_values
 }
 
-pub fn get_aperture(exif: &Exif) -> String {
-    let field = exif.get_field(Tag::FNumber, In::PRIMARY);
-    match field {
-        Some(value) => format!("{}", value.display_value().with_unit(exif)),
-        None => "N/A".to_string(),
-    }
+/// Fields read from the metadata by the framer itself
+pub fn get_builtin_fields() -> Vec<Field> {
+    vec![
+        Field::number(
+            "shutter_speed",
+            Some("shutter-speed-icon.svg"),
+            |metadata, _, _| get_number(metadata.exif, Tag::ExposureTime),
+            |seconds, config| format_shutter_speed(seconds, config.shutter_speed_format),
+        ),
+        Field::number(
+            "aperture",
+            Some("aperture-icon.svg"),
+            |metadata, _, _| get_number(metadata.exif, Tag::FNumber),
+            |f_number, config| format_aperture(f_number, config.aperture_format),
+        ),
+        Field::text(
+            "focal_length",
+            Some("focal-length-icon.svg"),
+            |metadata, config, _| {
+                let (actual, equivalent) =
+                    get_focal_lengths(metadata.exif, config, metadata.crop_factor);
+                format_focal_length(actual, equivalent, config.focal_length_display)
+            },
+        ),
+        Field::text(
+            "equivalent_focal_length",
+            Some("focal-length-icon.svg"),
+            |metadata, config, _| get_focal_lengths(metadata.exif, config, metadata.crop_factor).1,
+        ),
+        Field::text(
+            "equivalent_aperture",
+            Some("aperture-icon.svg"),
+            |metadata, config, _| {
+                get_equivalent_aperture(metadata.exif, metadata.crop_factor, config.aperture_format)
+            },
+        ),
+        // Values of the film profile replace the ones of the scanner
+        Field::text("iso", Some("iso-icon.svg"), |metadata, config, _| {
+            get_profile(config)
+                .and_then(|profile| profile.iso)
+                .map(|iso| format_iso(iso, config.iso_format))
+                .or_else(|| get_iso(metadata.exif, config))
+        }),
+        Field::text(
+            "exposure_bias",
+            Some("exposure-bias-icon.svg"),
+            |metadata, _, _| get_exposure_bias(metadata.exif),
+        ),
+        Field::text(
+            "metering_mode",
+            Some("metering-mode-icon.svg"),
+            |metadata, _, _| get_metering_mode(metadata.exif),
+        ),
+        Field::text("flash", Some("flash-icon.svg"), |metadata, _, _| {
+            get_flash(metadata.exif)
+        }),
+        Field::text(
+            "white_balance",
+            Some("white-balance-icon.svg"),
+            |metadata, _, _| get_white_balance(metadata.exif),
+        ),
+        Field::text(
+            "exposure_program",
+            Some("exposure-program-icon.svg"),
+            |metadata, _, _| get_exposure_program(metadata.exif),
+        ),
+        Field::text("camera", Some("camera-icon.svg"), |metadata, config, _| {
+            get_profile(config)
+                .and_then(|profile| profile.camera.clone())
+                .or_else(|| get_camera(metadata.exif, metadata.xmp, config))
+        }),
+        Field::text(
+            "lens",
+            Some("lens-icon.svg"),
+            |metadata, config, resources| {
+                get_profile(config)
+                    .and_then(|profile| profile.lens.clone())
+                    .or_else(|| {
+                        get_lens(
+                            metadata.exif,
+                            metadata.xmp,
+                            metadata.maker_note,
+                            &resources.lenses,
+                        )
+                    })
+            },
+        ),
+        Field::text("film_stock", Some("film-stock-icon.svg"), |_, config, _| {
+            get_profile(config).and_then(|profile| profile.film_stock.clone())
+        }),
+        Field::text(
+            "film_simulation",
+            Some("picture-style-icon.svg"),
+            |metadata, _, _| metadata.maker_note.and_then(makernote::get_film_simulation),
+        ),
+        Field::text(
+            "picture_style",
+            Some("picture-style-icon.svg"),
+            |metadata, _, _| metadata.maker_note.and_then(makernote::get_picture_style),
+        ),
+        Field::text(
+            "creative_look",
+            Some("picture-style-icon.svg"),
+            |metadata, _, _| metadata.maker_note.and_then(makernote::get_creative_look),
+        ),
+        Field::number(
+            "shutter_count",
+            Some("shutter-speed-icon.svg"),
+            |metadata, _, _| {
+                let count = metadata.maker_note.and_then(makernote::get_shutter_count)?;
+                Some(f64::from(count))
+            },
+            |count, _| Some(format!("{}", count)),
+        ),
+        Field::text(
+            "latitude",
+            Some("latitude-icon.svg"),
+            |metadata, config, _| {
+                let position = metadata.position?;
+                Some(format_latitude(
+                    position.coordinates.latitude,
+                    config.gps_format,
+                ))
+            },
+        ),
+        Field::text(
+            "longitude",
+            Some("longitude-icon.svg"),
+            |metadata, config, _| {
+                let position = metadata.position?;
+                Some(format_longitude(
+                    position.coordinates.longitude,
+                    config.gps_format,
+                ))
+            },
+        ),
+        Field::number(
+            "altitude",
+            Some("altitude-icon.svg"),
+            |metadata, _, _| metadata.position?.altitude,
+            |altitude, _| Some(format_altitude(altitude)),
+        ),
+        Field::number(
+            "direction",
+            Some("direction-icon.svg"),
+            |metadata, _, _| get_image_direction(metadata.exif),
+            |direction, config| Some(format_direction(direction, config.gps_format)),
+        ),
+        Field::text(
+            "location",
+            Some("location-icon.svg"),
+            |metadata, _, resources| {
+                let position = metadata.position?;
+                resources.geocoder.as_ref()?.reverse(position.coordinates)
+            },
+        ),
+        Field::text(
+            "date_taken",
+            Some("date-taken-icon.svg"),
+            |metadata, config, _| format_date_taken(&metadata.date_taken?, &config.date_format),
+        ),
+        Field::text("title", None, |metadata, _, _| {
+            metadata.xmp.get_text("dc:title")
+        }),
+        Field::text("description", None, |metadata, _, _| {
+            get_description(metadata, "dc:description", "Caption-Abstract")
+        }),
+        Field::text("rating", None, |metadata, _, _| get_rating(metadata.xmp)),
+        Field::text("label", None, |metadata, _, _| {
+            metadata.xmp.get_text("xmp:Label")
+        }),
+        Field::text("keywords", None, |metadata, _, _| {
+            get_description(metadata, "dc:subject", "Keywords")
+        }),
+        Field::text("headline", None, |metadata, _, _| {
+            get_description(metadata, "photoshop:Headline", "Headline")
+        }),
+        Field::text("creator", None, |metadata, _, _| get_creator(metadata)),
+        Field::text("copyright", None, |metadata, _, _| get_copyright(metadata)),
+        Field::text("city", None, |metadata, _, _| {
+            get_description(metadata, "photoshop:City", "City")
+        }),
+        Field::text("country", None, |metadata, _, _| {
+            get_description(metadata, "photoshop:Country", "Country")
+        }),
+        Field::text(
+            "signature",
+            Some("signature-icon.svg"),
+            |metadata, config, _| {
+                let creator = get_creator(metadata);
+                let copyright = get_copyright(metadata);
+                get_signature(
+                    config.signature.as_deref(),
+                    &SignatureValues {
+                        artist: creator.as_deref(),
+                        copyright: copyright.as_deref(),
+                        year: metadata
+                            .date_taken
+                            .map(|date_taken| date_taken.local.year()),
+                    },
+                )
+            },
+        ),
+    ]
 }
 
-pub fn get_focal_length(exif: &Exif) -> String {
-    let field = exif.get_field(Tag::FocalLength, In::PRIMARY);
-    match field {
-        Some(value) => format!("{}", value.display_value().with_unit(exif)).replace(" ", ""),
-        None => "N/A".to_string(),
-    }
+/// Reads ASCII field without the quotes added by `display_value`
+pub fn get_string(exif: &Exif, tag: Tag) -> Option<String> {
+    exif.get_field(tag, In::PRIMARY)
+        .map(|field| {
+            field
+                .display_value()
+                .to_string()
+                .trim_matches('"')
+                .trim()
+                .to_string()
+        })
+        .filter(|value| !value.is_empty())
+}
+
+/// Reads the first value of integer field
+pub fn get_uint(exif: &Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+/// Reads the first number of rational or integer field
+pub fn get_number(exif: &Exif, tag: Tag) -> Option<f64> {
+    let value = &exif.get_field(tag, In::PRIMARY)?.value;
+    let number = match value {
+        Value::Rational(numbers) => numbers.first()?.to_f64(),
+        Value::SRational(numbers) => numbers.first()?.to_f64(),
+        _ => f64::from(value.get_uint(0)?),
+    };
+    Some(number).filter(|number| number.is_finite())
+}
+
+pub fn get_focal_length(exif: &Exif) -> Option<String> {
+    let field = exif.get_field(Tag::FocalLength, In::PRIMARY)?;
+    Some(format!("{}", field.display_value().with_unit(exif)).replace(" ", ""))
+}
+
+/// Camera edited in XMP takes precedence over the one in EXIF
+pub fn get_camera(exif: &Exif, xmp: &Xmp, config: &Config) -> Option<String> {
+    let brand = xmp
+        .get_text("tiff:Make")
+        .or_else(|| get_string(exif, Tag::Make))?;
+    let model = xmp
+        .get_text("tiff:Model")
+        .or_else(|| get_string(exif, Tag::Model))?;
+    Some(get_camera_name(&brand, &model, &config.camera_aliases))
+}
+
+/// Lens edited in XMP, then the lens from EXIF and the one decoded from the maker note
+pub fn get_lens(
+    exif: &Exif,
+    xmp: &Xmp,
+    maker_note: Option<&MakerNote>,
+    lenses: &LensDatabase,
+) -> Option<String> {
+    let xmp_lens = join_lens(
+        xmp.get_text("exifEX:LensMake"),
+        xmp.get_text("exifEX:LensModel")
+            .or_else(|| xmp.get_text("aux:Lens")),
+    );
+    // Some bodies write only the lens ID or "----" to the model, the maker note is used instead
+    let exif_lens = || {
+        join_lens(
+            get_string(exif, Tag::LensMake),
+            get_string(exif, Tag::LensModel).filter(|model| model.chars().any(char::is_alphabetic)),
+        )
+    };
+
+    xmp_lens
+        .or_else(exif_lens)
+        .or_else(|| maker_note.and_then(|maker_note| makernote::get_lens(maker_note, lenses)))
 }
 
-pub fn get_iso(exif: &Exif) -> String {
-    let field = exif.get_field(Tag::PhotographicSensitivity, In::PRIMARY);
-    match field {
-        Some(value) => format!("{}", value.display_value().with_unit(exif)),
-        None => "N/A".to_string(),
+fn join_lens(make: Option<String>, model: Option<String>) -> Option<String> {
+    match (make, model) {
+        (Some(make), Some(model)) if !model.starts_with(&make) => {
+            Some(format!("{} {}", make, model))
+        }
+        (_, model) => model,
     }
 }
 
-pub fn get_camera(exif: &Exif) -> String {
-    let brand = exif.get_field(Tag::Make, In::PRIMARY);
-    let model = exif.get_field(Tag::Model, In::PRIMARY);
+/// Descriptive information from XMP, or from IPTC for the files not synchronized with XMP
+fn get_description(metadata: &Metadata, xmp_name: &str, iptc_name: &str) -> Option<String> {
+    metadata
+        .xmp
+        .get_text(xmp_name)
+        .or_else(|| metadata.iptc.get_text(iptc_name))
+}
 
-    match (brand, model) {
-        (Some(brand), Some(model)) => format!(
-            "{} {}",
-            brand
-                .display_value()
-                .with_unit(exif)
-                .to_string()
-                .trim_matches('"'),
-            model
-                .display_value()
-                .with_unit(exif)
-                .to_string()
-                .trim_matches('"')
-        ),
-        _ => "N/A".to_string(),
+fn get_creator(metadata: &Metadata) -> Option<String> {
+    get_description(metadata, "dc:creator", "By-line")
+        .or_else(|| get_string(metadata.exif, Tag::Artist))
+}
+
+fn get_copyright(metadata: &Metadata) -> Option<String> {
+    get_description(metadata, "dc:rights", "CopyrightNotice")
+        .or_else(|| get_string(metadata.exif, Tag::Copyright))
+}
+
+/// Stars for the rating from 1 to 5, -1 marks rejected images
+pub fn get_rating(xmp: &Xmp) -> Option<String> {
+    let rating = xmp.get_text("xmp:Rating")?.parse::<f64>().ok()?.round() as i32;
+    match rating {
+        -1 => Some("Rejected".to_string()),
+        1..=5 => Some(format!(
+            "{}{}",
+            "★".repeat(rating as usize),
+            "☆".repeat(5 - rating as usize)
+        )),
+        _ => None,
     }
 }
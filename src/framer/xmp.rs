@@ -0,0 +1,223 @@
+use anyhow::Context;
+use log::debug;
+use roxmltree::{Document, Node};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum XmpValue {
+    Text(String),
+    /// Items of `rdf:Bag` or `rdf:Seq`
+    List(Vec<String>),
+}
+
+/// Simple XMP properties keyed by their prefixed name like `dc:title`
+///
+/// Structures are skipped, language alternatives are reduced to the default language.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Xmp {
+    properties: BTreeMap<String, XmpValue>,
+}
+
+impl Xmp {
+    /// Reads the XMP embedded in the image and the sidecar next to it
+    ///
+    /// Properties of the sidecar take precedence over the embedded ones.
+    pub fn load(path: &Path, bytes: &[u8]) -> Result<Xmp, anyhow::Error> {
+        let embedded = find_xmp_packet(bytes)
+            .and_then(|packet| match Xmp::parse(&packet) {
+                Ok(xmp) => Some(xmp),
+                Err(error) => {
+                    debug!("Invalid XMP packet embedded in {:?}: {}", path, error);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        let sidecar = match get_sidecar_path(path) {
+            Some(sidecar_path) => {
+                debug!("Reading XMP sidecar {:?}", sidecar_path);
+                let content = fs::read_to_string(&sidecar_path)
+                    .with_context(|| format!("could not read XMP sidecar `{:?}`", sidecar_path))?;
+                Xmp::parse(&content)
+                    .with_context(|| format!("invalid XMP sidecar `{:?}`", sidecar_path))?
+            }
+            None => Xmp::default(),
+        };
+
+        Ok(embedded.merge(sidecar))
+    }
+
+    pub fn parse(content: &str) -> Result<Xmp, anyhow::Error> {
+        let document = Document::parse(content)?;
+        let mut properties = BTreeMap::new();
+        for description in document
+            .descendants()
+            .filter(|node| is_rdf(node, "Description"))
+        {
+            for attribute in description.attributes() {
+                let Some(namespace) = attribute.namespace() else {
+                    continue;
+                };
+                if namespace == RDF_NAMESPACE {
+                    continue;
+                }
+                let name = get_prefixed_name(&description, namespace, attribute.name());
+                properties.insert(name, XmpValue::Text(attribute.value().trim().to_string()));
+            }
+            for property in description.children().filter(|node| node.is_element()) {
+                let Some(namespace) = property.tag_name().namespace() else {
+                    continue;
+                };
+                if let Some(value) = get_value(&property) {
+                    let name = get_prefixed_name(&property, namespace, property.tag_name().name());
+                    properties.insert(name, value);
+                }
+            }
+        }
+        properties.retain(|_, value| match value {
+            XmpValue::Text(text) => !text.is_empty(),
+            XmpValue::List(items) => !items.is_empty(),
+        });
+        Ok(Xmp { properties })
+    }
+
+    /// Adds the properties of `other` overriding the existing ones
+    pub fn merge(mut self, other: Xmp) -> Xmp {
+        self.properties.extend(other.properties);
+        self
+    }
+
+    /// Text of the property, items of a list are joined with a comma
+    pub fn get_text(&self, name: &str) -> Option<String> {
+        match self.properties.get(name)? {
+            XmpValue::Text(text) => Some(text.clone()),
+            XmpValue::List(items) => Some(items.join(", ")),
+        }
+    }
+}
+
+/// Finds XMP packet embedded in the image file
+pub fn find_xmp_packet(bytes: &[u8]) -> Option<String> {
+    let start = find_subslice(bytes, b"<x:xmpmeta")?;
+    let end_tag = b"</x:xmpmeta>";
+    let end = start + find_subslice(&bytes[start..], end_tag)? + end_tag.len();
+    Some(String::from_utf8_lossy(&bytes[start..end]).to_string())
+}
+
+/// Sidecar named either `IMG_1234.xmp` (Lightroom) or `IMG_1234.jpg.xmp` (darktable)
+pub fn get_sidecar_path(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let file_stem = path.file_stem()?.to_str()?;
+    [file_stem, file_name]
+        .iter()
+        .flat_map(|name| [format!("{}.xmp", name), format!("{}.XMP", name)])
+        .map(|sidecar_name| path.with_file_name(sidecar_name))
+        .find(|sidecar_path| sidecar_path.is_file())
+}
+
+fn get_value(property: &Node) -> Option<XmpValue> {
+    let Some(container) = property.children().find(|node| node.is_element()) else {
+        return Some(XmpValue::Text(property.text()?.trim().to_string()));
+    };
+    let items = container
+        .children()
+        .filter(|node| is_rdf(node, "li"))
+        .filter_map(|item| Some((item, item.text()?.trim().to_string())));
+
+    if is_rdf(&container, "Alt") {
+        let items: Vec<(Node, String)> = items.collect();
+        let default = items
+            .iter()
+            .find(|(item, _)| {
+                item.attribute(("http://www.w3.org/XML/1998/namespace", "lang"))
+                    == Some("x-default")
+            })
+            .or(items.first());
+        default.map(|(_, text)| XmpValue::Text(text.clone()))
+    } else if is_rdf(&container, "Bag") || is_rdf(&container, "Seq") {
+        Some(XmpValue::List(items.map(|(_, text)| text).collect()))
+    } else {
+        None
+    }
+}
+
+fn get_prefixed_name(node: &Node, namespace: &str, name: &str) -> String {
+    match node.lookup_prefix(namespace) {
+        Some(prefix) => format!("{}:{}", prefix, name),
+        None => name.to_string(),
+    }
+}
+
+fn is_rdf(node: &Node, name: &str) -> bool {
+    node.tag_name().namespace() == Some(RDF_NAMESPACE) && node.tag_name().name() == name
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[test]
+fn test_parse_xmp() {
+    let embedded = Xmp::parse(
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+          <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description rdf:about=""
+                xmlns:aux="http://ns.adobe.com/exif/1.0/aux/"
+                xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+                aux:Lens="XF23mmF2 R WR" xmp:Rating="3"/>
+          </rdf:RDF>
+        </x:xmpmeta>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        embedded.get_text("aux:Lens"),
+        Some("XF23mmF2 R WR".to_string())
+    );
+
+    let sidecar = Xmp::parse(
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+          <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description rdf:about=""
+                xmlns:dc="http://purl.org/dc/elements/1.1/"
+                xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+              <xmp:Rating>5</xmp:Rating>
+              <dc:title>
+                <rdf:Alt>
+                  <rdf:li xml:lang="en-US">Sunset</rdf:li>
+                  <rdf:li xml:lang="x-default">Sunset over Danube</rdf:li>
+                </rdf:Alt>
+              </dc:title>
+              <dc:subject>
+                <rdf:Bag><rdf:li>river</rdf:li><rdf:li>sunset</rdf:li></rdf:Bag>
+              </dc:subject>
+            </rdf:Description>
+          </rdf:RDF>
+        </x:xmpmeta>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        sidecar.get_text("dc:title"),
+        Some("Sunset over Danube".to_string())
+    );
+    assert_eq!(
+        sidecar.get_text("dc:subject"),
+        Some("river, sunset".to_string())
+    );
+
+    let merged = embedded.merge(sidecar);
+    assert_eq!(merged.get_text("xmp:Rating"), Some("5".to_string()));
+    assert_eq!(
+        merged.get_text("aux:Lens"),
+        Some("XF23mmF2 R WR".to_string())
+    );
+    assert_eq!(merged.get_text("aux:SerialNumber"), None);
+}
@@ -1,17 +1,45 @@
 use anyhow::{Context, Result};
+use chrono::{NaiveDateTime, TimeDelta};
 use clap::Parser;
+use exif::Exif;
 use handlebars::Handlebars;
 use log::{debug, error};
 use resolution::Resolution;
 use std::{
-    fs::File,
-    io::BufReader,
+    fs,
+    io::Cursor,
     path::{Path, PathBuf},
 };
-use templates::{copy_default_template, init_templates_if_needed, register_templates};
+use templates::{
+    copy_default_template, get_templates_path, init_templates_if_needed, load_template_manifest,
+    register_templates,
+};
 
-use crate::resolution::get_frame_width;
+use crate::{
+    config::{load_config, Config},
+    framer::{
+        date_time::{get_date_taken, TimeShift, TimeSpan, Timezone},
+        fields::{check_custom_fields, FieldRegistry},
+        focal_length::FocalLengthDisplay,
+        font::Font,
+        formatter::{ApertureFormat, IsoFormat, ShutterSpeedFormat},
+        geocoding::Geocoder,
+        gps::GpsFormat,
+        gpx::Track,
+        iptc::Iptc,
+        layout::{check_display_orders, is_displayed},
+        lens_database::LensDatabase,
+        missing_value::{check_missing_values, MissingValue},
+        overrides::{Override, Overrides},
+        positions::{get_row_count, Overflow},
+        profile::get_profile,
+        xmp::Xmp,
+        Resources,
+    },
+    resolution::get_frame_width,
+};
 
+mod config;
 mod framer;
 mod resolution;
 mod templates;
@@ -29,7 +57,7 @@ struct CliArgs {
     #[arg(short, long)]
     portrait: bool,
 
-    /// Height that should be taken by the frame
+    /// Height that should be taken by a row of the frame
     #[arg(long = "height", default_value_t = 40)]
     frame_height: u8,
 
@@ -45,6 +73,121 @@ struct CliArgs {
     #[arg(short, long)]
     inset: bool,
 
+    /// Displays GPS coordinates, altitude and direction in the frame
+    #[arg(long)]
+    show_gps: bool,
+
+    /// Displays the place name resolved offline from GPS coordinates in the frame
+    #[arg(long)]
+    show_location: bool,
+
+    /// Displays the capture date and time in the frame
+    #[arg(long)]
+    show_date: bool,
+
+    /// strftime-like format of the capture date, e.g. "%d. %m. %Y", overrides `date-format` from the config file
+    #[arg(long)]
+    date_format: Option<String>,
+
+    /// Timezone (Europe/Bratislava or +02:00) of images without the offset of the capture time, overrides `timezone` from the config file
+    #[arg(long)]
+    timezone: Option<Timezone>,
+
+    /// Corrects the camera clock, either by an offset (+1h23m, -1d) or by the date and time
+    /// when the first image was actually taken (2024-07-14 18:05:00)
+    #[arg(long, allow_hyphen_values = true)]
+    time_shift: Option<TimeShift>,
+
+    /// GPX tracks used to geotag images without GPS coordinates
+    #[arg(long)]
+    gpx: Vec<PathBuf>,
+
+    /// Maximum time between GPX track points to interpolate the position (5m, 30s), overrides `gpx-max-gap` from the config file
+    #[arg(long)]
+    gpx_max_gap: Option<TimeSpan>,
+
+    /// Difference between the camera clock and the GPX track (+1h, -30s), overrides `gpx-time-offset` from the config file
+    #[arg(long, allow_hyphen_values = true)]
+    gpx_time_offset: Option<TimeSpan>,
+
+    /// Format of GPS coordinates and image direction, overrides `gps-format` from the config file
+    #[arg(long, value_enum)]
+    gps_format: Option<GpsFormat>,
+
+    /// Displays the actual, the 35mm-equivalent focal length or both of them, overrides `focal-length-display` from the config file
+    #[arg(long, value_enum)]
+    focal_length: Option<FocalLengthDisplay>,
+
+    /// Displays the 35mm-equivalent aperture next to the actual one
+    #[arg(long)]
+    show_equivalent_aperture: bool,
+
+    /// Displays exposure compensation, exposure program, metering mode, flash and white balance in the frame
+    #[arg(long)]
+    show_exposure: bool,
+
+    /// Displays the film simulation or picture style and the shutter count decoded from the maker note
+    #[arg(long)]
+    show_maker_note: bool,
+
+    /// Displays the signature built from the artist and copyright in the middle of the frame
+    #[arg(long)]
+    show_signature: bool,
+
+    /// Signature with `{artist}`, `{copyright}` and `{year}` placeholders, e.g. "© {year} {artist}", implies --show-signature
+    #[arg(long)]
+    signature: Option<String>,
+
+    /// Format of the shutter speed, overrides `shutter-speed-format` from the config file
+    #[arg(long, value_enum)]
+    shutter_speed_format: Option<ShutterSpeedFormat>,
+
+    /// Format of the aperture, overrides `aperture-format` from the config file
+    #[arg(long, value_enum)]
+    aperture_format: Option<ApertureFormat>,
+
+    /// Format of the ISO, overrides `iso-format` from the config file
+    #[arg(long, value_enum)]
+    iso_format: Option<IsoFormat>,
+
+    /// Adds "(ext)" to ISO speeds outside of the native range configured for the camera
+    #[arg(long)]
+    mark_extended_iso: bool,
+
+    /// Text displayed for values missing in the metadata, `hide` leaves them out of the frame
+    /// and `error` fails the image. Overrides `missing-value` from the config file
+    #[arg(long)]
+    missing_value: Option<MissingValue>,
+
+    /// Film profile with the camera, lens, film stock and ISO used instead of the scanner's EXIF,
+    /// overrides `profile` from the config file
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Sets the value of a field (lens="Helios 44-2 58mm f/2"), takes precedence over
+    /// the metadata and `.metaframer.toml` sidecars. Empty value handles the field as missing
+    #[arg(long, value_name = "KEY=VALUE")]
+    set: Vec<Override>,
+
+    /// Scales the font down, truncates or drops values from the end of the groups or wraps the right group
+    /// to the second row when the values don't fit into the frame, overrides `overflow` from the config file
+    #[arg(long, value_enum)]
+    overflow: Option<Overflow>,
+
+    /// Fields displayed from the left edge of the frame (camera,lens,date_taken),
+    /// overrides `left` from the config file and the template
+    #[arg(long, value_delimiter = ',')]
+    left: Option<Vec<String>>,
+
+    /// Fields displayed in the middle of the frame (signature), overrides `center` from the config file and the template
+    #[arg(long, value_delimiter = ',')]
+    center: Option<Vec<String>>,
+
+    /// Fields displayed up to the right edge of the frame (aperture,shutter_speed,iso),
+    /// overrides `right` from the config file and the template
+    #[arg(long, value_delimiter = ',')]
+    right: Option<Vec<String>>,
+
     #[command(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 }
@@ -60,8 +203,115 @@ fn main() -> Result<()> {
         copy_default_template()?;
     }
 
+    let mut config = load_config()?;
+    if args.show_gps {
+        config.show_gps = true;
+    }
+    if args.show_location {
+        config.show_location = true;
+    }
+    if args.show_date {
+        config.show_date = true;
+    }
+    if let Some(date_format) = &args.date_format {
+        config.date_format = date_format.clone();
+    }
+    if let Some(timezone) = &args.timezone {
+        config.timezone = Some(timezone.clone());
+    }
+    config.time_shift = match args.time_shift {
+        Some(TimeShift::Offset(offset)) => Some(offset.0),
+        Some(TimeShift::ActualTime(actual_time)) => {
+            Some(get_reference_time_shift(&args.paths, &config, actual_time)?)
+        }
+        None => None,
+    };
+    if let Some(gpx_max_gap) = args.gpx_max_gap {
+        config.gpx_max_gap = gpx_max_gap;
+    }
+    if let Some(gpx_time_offset) = args.gpx_time_offset {
+        config.gpx_time_offset = gpx_time_offset;
+    }
+    if let Some(gps_format) = args.gps_format {
+        config.gps_format = gps_format;
+    }
+    if let Some(focal_length) = args.focal_length {
+        config.focal_length_display = focal_length;
+    }
+    if args.show_equivalent_aperture {
+        config.show_equivalent_aperture = true;
+    }
+    if args.show_exposure {
+        config.show_exposure = true;
+    }
+    if args.show_maker_note {
+        config.show_maker_note = true;
+    }
+    if args.show_signature {
+        config.show_signature = true;
+    }
+    if let Some(signature) = &args.signature {
+        config.signature = Some(signature.clone());
+        config.show_signature = true;
+    }
+    if let Some(shutter_speed_format) = args.shutter_speed_format {
+        config.shutter_speed_format = shutter_speed_format;
+    }
+    if let Some(aperture_format) = args.aperture_format {
+        config.aperture_format = aperture_format;
+    }
+    if let Some(iso_format) = args.iso_format {
+        config.iso_format = iso_format;
+    }
+    if args.mark_extended_iso {
+        config.mark_extended_iso = true;
+    }
+    if let Some(missing_value) = &args.missing_value {
+        config.missing_value = missing_value.clone();
+    }
+    if let Some(profile) = &args.profile {
+        config.profile = Some(profile.clone());
+    }
+    if let Some(profile) = &config.profile {
+        get_profile(&config)
+            .with_context(|| format!("film profile `{}` is not configured", profile))?;
+    }
+    if let Some(overflow) = args.overflow {
+        config.overflow = overflow;
+    }
+    let manifest = load_template_manifest(&args.template_name)?;
+    config.left = args.left.clone().or(config.left).or(manifest.left);
+    config.center = args.center.clone().or(config.center).or(manifest.center);
+    config.right = args.right.clone().or(config.right).or(manifest.right);
+    if config.rows.is_empty() {
+        config.rows = manifest.rows;
+    }
+    debug!("Config: {:?}", config);
+    let mut fields = FieldRegistry::load(&config);
+    fields.remove_missing_icons(&get_templates_path(&args.template_name));
+    let resources = Resources {
+        geocoder: match is_displayed(&config, "location", config.show_location) {
+            true => Some(Geocoder::load(&config)?),
+            false => None,
+        },
+        fields,
+        lenses: LensDatabase::load(&config),
+        track: match args.gpx.is_empty() {
+            true => None,
+            false => Some(Track::load(&args.gpx)?),
+        },
+        font: manifest
+            .font
+            .as_ref()
+            .and_then(|font| Font::load(font, &config, &get_templates_path(&args.template_name))),
+    };
+
+    check_custom_fields(&config)?;
+    check_display_orders(&config, &resources.fields)?;
+    check_missing_values(&config, &resources.fields)?;
+
     let mut handlebars = Handlebars::new();
-    register_templates(&args.template_name, &mut handlebars)?;
+    register_templates(&args.template_name, &resources.fields, &mut handlebars)?;
 
     debug!("Files: {:?}", args.paths);
     debug!("Resolution: {:?}", args.resolution);
@@ -70,7 +320,7 @@ fn main() -> Result<()> {
     let paths = args.paths.clone();
 
     for path in &paths {
-        match process_file(&handlebars, &args, path) {
+        match process_file(&handlebars, &args, &config, &resources, path) {
             Ok(..) => {}
             Err(error) => {
                 error!("{:?}", error)
@@ -81,6 +331,35 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Computes the time shift from the difference between the capture time of the first image
+/// and the time it was actually taken
+fn get_reference_time_shift(
+    paths: &[PathBuf],
+    config: &Config,
+    actual_time: NaiveDateTime,
+) -> Result<TimeDelta> {
+    let path = paths
+        .first()
+        .context("time shift with the actual time requires at least one image")?;
+    let (_, exif) = read_image(path)?;
+    let date_taken = get_date_taken(&exif, config.timezone.as_ref())
+        .with_context(|| format!("file `{:?}` doesn't contain the capture time", path))?;
+    debug!(
+        "Reference image {:?} was taken at {}, actually {}",
+        path, date_taken.local, actual_time
+    );
+    Ok(actual_time - date_taken.local)
+}
+
+/// Reads the file with its EXIF, the bytes are kept for the embedded IPTC and XMP
+fn read_image(path: &Path) -> Result<(Vec<u8>, Exif)> {
+    let bytes = fs::read(path).with_context(|| format!("could not read file `{:?}`", path))?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(&bytes))
+        .with_context(|| format!("file `{:?}` is not a valid image", path))?;
+    Ok((bytes, exif))
+}
+
 fn get_frame_path(path: &Path) -> PathBuf {
     let mut frame_path = path.to_path_buf();
     let orig_file_stem = path.file_stem().unwrap();
@@ -90,13 +369,15 @@ fn get_frame_path(path: &Path) -> PathBuf {
     frame_path
 }
 
-fn process_file(handlebars: &Handlebars<'_>, args: &CliArgs, path: &Path) -> Result<()> {
-    let file = File::open(path).with_context(|| format!("could not read file `{:?}`", path))?;
-    let mut bufreader = BufReader::new(&file);
-    let exifreader = exif::Reader::new();
-    let exif = exifreader
-        .read_from_container(&mut bufreader)
-        .with_context(|| format!("file `{:?}` is not a valid image", path))?;
+fn process_file(
+    handlebars: &Handlebars<'_>,
+    args: &CliArgs,
+    config: &Config,
+    resources: &Resources,
+    path: &Path,
+) -> Result<()> {
+    let (bytes, exif) = read_image(path)?;
+    let iptc = Iptc::read(&bytes);
     for f in exif.fields() {
         debug!(
             "{} {} {}",
@@ -107,11 +388,36 @@ fn process_file(handlebars: &Handlebars<'_>, args: &CliArgs, path: &Path) -> Res
     }
 
     let dimensions = image::image_dimensions(path)?;
-    let excluded_height = if args.inset { 0 } else { args.frame_height };
-    let frame_width = get_frame_width(args.resolution, args.portrait, dimensions, excluded_height);
-    let frame_data = framer::get_frame_data((frame_width, args.frame_height as u32), &exif)?;
+    let xmp = Xmp::load(path, &bytes)?;
+    let overrides = Overrides::load(path, &args.set)?;
+    let mut row_count = config.rows.len().max(1) as u32;
+    let frame_data = loop {
+        let excluded_height = match args.inset {
+            true => 0,
+            false => args.frame_height as u32 * row_count,
+        };
+        let frame_width =
+            get_frame_width(args.resolution, args.portrait, dimensions, excluded_height)
+                .with_context(|| format!("could not fit the frame of `{:?}`", path))?;
+        let frame_data = framer::get_frame_data(
+            (frame_width, args.frame_height as u32),
+            &exif,
+            &xmp,
+            &iptc,
+            &overrides,
+            config,
+            resources,
+        )?;
+        // Rows added by the wrapped values shrink the image, the values are laid out again
+        let frame_row_count = get_row_count(&frame_data.values);
+        if args.inset || frame_row_count <= row_count {
+            break frame_data;
+        }
+        row_count = frame_row_count;
+    };
 
-    let mut output_file = File::create(get_frame_path(path))?;
-    handlebars.render_to_write("main", &frame_data, &mut output_file)?;
+    // Rendered first so a failed render doesn't leave a truncated frame behind
+    let frame = handlebars.render("main", &frame_data)?;
+    fs::write(get_frame_path(path), frame)?;
     Ok(())
 }
@@ -1,3 +1,4 @@
+use anyhow::{bail, Result};
 use clap::{builder::PossibleValue, ValueEnum};
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Debug)]
@@ -52,39 +53,44 @@ pub fn get_frame_width(
     resolution: Resolution,
     is_portrait: bool,
     (o_width, o_height): (u32, u32),
-    excluded_height: u8,
-) -> u32 {
+    excluded_height: u32,
+) -> Result<u32> {
     let frame_dimensions = resolution.dimensions();
 
     // Rotate if we are generating for portrait frame
     let (width, height) = if is_portrait {
-        (
-            frame_dimensions.1,
-            frame_dimensions.0 - u32::from(excluded_height),
-        )
+        (frame_dimensions.1, frame_dimensions.0)
     } else {
-        (
-            frame_dimensions.0,
-            frame_dimensions.1 - u32::from(excluded_height),
-        )
+        (frame_dimensions.0, frame_dimensions.1)
     };
+    let height = match height.checked_sub(excluded_height) {
+        Some(height) if height > 0 => height,
+        _ => bail!(
+            "the frame of {}px leaves no space for the image in the {}px high resolution",
+            excluded_height,
+            height
+        ),
+    };
+    if o_width == 0 || o_height == 0 {
+        bail!("the image has no width or height");
+    }
 
     let has_bigger_aspect_ratio = o_width / o_height > width / height;
 
-    match has_bigger_aspect_ratio {
+    Ok(match has_bigger_aspect_ratio {
         false => {
             let scale = f64::from(height) / f64::from(o_height);
             (scale * f64::from(o_width)) as u32
         }
         true => width,
-    }
+    })
 }
 
 #[test]
 fn test_get_frame_width_with_portrait_for_landscape() {
     use log::debug;
     let portrait_dimensions = (760, 1280);
-    let frame_width = get_frame_width(Resolution::FullHD, false, portrait_dimensions, 40);
+    let frame_width = get_frame_width(Resolution::FullHD, false, portrait_dimensions, 40).unwrap();
     let x = (1080. - 40.) / 1280.0;
     let expected_width = 760. * x;
     debug!("expected width: {}", expected_width);
@@ -95,7 +101,7 @@ fn test_get_frame_width_with_portrait_for_landscape() {
 fn test_get_frame_width_with_portrait_for_portait() {
     use log::debug;
     let portrait_dimensions = (760, 1280);
-    let frame_width = get_frame_width(Resolution::FullHD, true, portrait_dimensions, 40);
+    let frame_width = get_frame_width(Resolution::FullHD, true, portrait_dimensions, 40).unwrap();
     let x = (1920. - 40.) / 1280.;
     let expected_width = 760. * x;
     debug!("expected width: {}", expected_width);
@@ -105,7 +111,7 @@ fn test_get_frame_width_with_portrait_for_portait() {
 #[test]
 fn test_get_frame_width_with_portrait_for_portrait_with_bigger_aspect_ratio() {
     let portrait_dimensions = (720, 1280);
-    let frame_width = get_frame_width(Resolution::FullHD, true, portrait_dimensions, 40);
+    let frame_width = get_frame_width(Resolution::FullHD, true, portrait_dimensions, 40).unwrap();
     let x = (1920. - 40.) / 1280.;
     let expected_width = 720. * x;
     assert_eq!(frame_width, expected_width as u32)
@@ -114,7 +120,7 @@ fn test_get_frame_width_with_portrait_for_portrait_with_bigger_aspect_ratio() {
 #[test]
 fn test_get_frame_width_with_landscape_for_landscape_with_bigger_aspect_ratio() {
     let landscape_dimensions = (1280, 720);
-    let frame_width = get_frame_width(Resolution::FullHD, false, landscape_dimensions, 40);
+    let frame_width = get_frame_width(Resolution::FullHD, false, landscape_dimensions, 40).unwrap();
     let x = (1080. - 40.) / 720.;
     let expected_width = 1280. * x;
     assert_eq!(frame_width, expected_width as u32)
@@ -123,7 +129,7 @@ fn test_get_frame_width_with_landscape_for_landscape_with_bigger_aspect_ratio()
 #[test]
 fn test_get_frame_width_with_landscape_for_portrait() {
     let landscape_dimensions = (1280, 720);
-    let frame_width = get_frame_width(Resolution::FullHD, true, landscape_dimensions, 40);
+    let frame_width = get_frame_width(Resolution::FullHD, true, landscape_dimensions, 40).unwrap();
     let expected_width = 1080.;
     assert_eq!(frame_width, expected_width as u32)
 }
@@ -131,7 +137,7 @@ fn test_get_frame_width_with_landscape_for_portrait() {
 #[test]
 fn test_get_frame_width_with_landscape_for_portrait_with_bigger_img() {
     let landscape_dimensions = (2000, 720);
-    let frame_width = get_frame_width(Resolution::FullHD, true, landscape_dimensions, 40);
+    let frame_width = get_frame_width(Resolution::FullHD, true, landscape_dimensions, 40).unwrap();
     let expected_width = 1080.;
     assert_eq!(frame_width, expected_width as u32)
 }
@@ -140,9 +146,18 @@ fn test_get_frame_width_with_landscape_for_portrait_with_bigger_img() {
 fn test_get_frame_width_with_portrait_for_landscape_with_bigger_img() {
     use log::debug;
     let portrait_dimensions = (2080, 3800);
-    let frame_width = get_frame_width(Resolution::FullHD, false, portrait_dimensions, 40);
+    let frame_width = get_frame_width(Resolution::FullHD, false, portrait_dimensions, 40).unwrap();
     let x = (1080. - 40.) / 3800.0;
     let expected_width = 2080. * x;
     debug!("expected width: {}", expected_width);
     assert_eq!(frame_width, expected_width as u32)
 }
+
+#[test]
+fn test_get_frame_width_with_frame_taller_than_resolution() {
+    let landscape_dimensions = (1280, 720);
+    assert!(get_frame_width(Resolution::HD, false, landscape_dimensions, 3 * 240).is_err());
+    assert!(get_frame_width(Resolution::HD, false, landscape_dimensions, 3 * 250).is_err());
+    assert!(get_frame_width(Resolution::HD, false, (0, 0), 40).is_err());
+    assert!(get_frame_width(Resolution::HD, true, landscape_dimensions, 3 * 250).is_ok());
+}
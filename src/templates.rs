@@ -1,10 +1,169 @@
 use anyhow::Context;
 use dirs::{self, config_dir};
-use handlebars::{self, Handlebars};
+use handlebars::{self, handlebars_helper, Handlebars};
 use log::debug;
+use serde::Deserialize;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use clap::ValueEnum;
+
+use crate::framer::{
+    date_time::{format_date_taken, parse_iso_string},
+    fields::FieldRegistry,
+    font::FontDescription,
+    formatter::{
+        format_aperture, format_iso, format_shutter_speed, ApertureFormat, IsoFormat,
+        ShutterSpeedFormat,
+    },
+    layout::DisplayOrders,
+};
+
+const DEFAULT_TEMPLATE_FILES: [(&str, &str); 22] = [
+    ("main.svg", include_str!("../templates/default/main.svg")),
+    (
+        "manifest.toml",
+        include_str!("../templates/default/manifest.toml"),
+    ),
+    (
+        "iso-icon.svg",
+        include_str!("../templates/default/iso-icon.svg"),
+    ),
+    (
+        "camera-icon.svg",
+        include_str!("../templates/default/camera-icon.svg"),
+    ),
+    (
+        "aperture-icon.svg",
+        include_str!("../templates/default/aperture-icon.svg"),
+    ),
+    (
+        "focal-length-icon.svg",
+        include_str!("../templates/default/focal-length-icon.svg"),
+    ),
+    (
+        "shutter-speed-icon.svg",
+        include_str!("../templates/default/shutter-speed-icon.svg"),
+    ),
+    (
+        "lens-icon.svg",
+        include_str!("../templates/default/lens-icon.svg"),
+    ),
+    (
+        "latitude-icon.svg",
+        include_str!("../templates/default/latitude-icon.svg"),
+    ),
+    (
+        "longitude-icon.svg",
+        include_str!("../templates/default/longitude-icon.svg"),
+    ),
+    (
+        "altitude-icon.svg",
+        include_str!("../templates/default/altitude-icon.svg"),
+    ),
+    (
+        "direction-icon.svg",
+        include_str!("../templates/default/direction-icon.svg"),
+    ),
+    (
+        "location-icon.svg",
+        include_str!("../templates/default/location-icon.svg"),
+    ),
+    (
+        "date-taken-icon.svg",
+        include_str!("../templates/default/date-taken-icon.svg"),
+    ),
+    (
+        "exposure-bias-icon.svg",
+        include_str!("../templates/default/exposure-bias-icon.svg"),
+    ),
+    (
+        "metering-mode-icon.svg",
+        include_str!("../templates/default/metering-mode-icon.svg"),
+    ),
+    (
+        "flash-icon.svg",
+        include_str!("../templates/default/flash-icon.svg"),
+    ),
+    (
+        "white-balance-icon.svg",
+        include_str!("../templates/default/white-balance-icon.svg"),
+    ),
+    (
+        "exposure-program-icon.svg",
+        include_str!("../templates/default/exposure-program-icon.svg"),
+    ),
+    (
+        "signature-icon.svg",
+        include_str!("../templates/default/signature-icon.svg"),
+    ),
+    (
+        "film-stock-icon.svg",
+        include_str!("../templates/default/film-stock-icon.svg"),
+    ),
+    (
+        "picture-style-icon.svg",
+        include_str!("../templates/default/picture-style-icon.svg"),
+    ),
+];
+
+// {{date date_taken "%d. %m. %Y"}} formats the ISO date with strftime-like format,
+// images without the capture time get an empty text
+handlebars_helper!(date: |value: Json, format: str| {
+    value
+        .as_str()
+        .and_then(parse_iso_string)
+        .and_then(|date_taken| format_date_taken(&date_taken, format))
+        .unwrap_or_default()
+});
+
+// {{shutter_speed exif.ExposureTime.raw "compact"}} formats the exposure time in seconds
+handlebars_helper!(shutter_speed: |seconds: Json, format: str| {
+    get_number(seconds)
+        .and_then(|seconds| {
+            format_shutter_speed(seconds, ShutterSpeedFormat::from_str(format, true).unwrap_or_default())
+        })
+        .unwrap_or_default()
+});
+
+// {{aperture exif.FNumber.raw "hook"}} formats the f-number
+handlebars_helper!(aperture: |f_number: Json, format: str| {
+    get_number(f_number)
+        .and_then(|f_number| {
+            format_aperture(f_number, ApertureFormat::from_str(format, true).unwrap_or_default())
+        })
+        .unwrap_or_default()
+});
+
+// {{iso exif.PhotographicSensitivity.raw "prefix"}} formats the ISO speed
+handlebars_helper!(iso: |speed: Json, format: str| {
+    get_number(speed)
+        .map(|speed| format_iso(speed as u32, IsoFormat::from_str(format, true).unwrap_or_default()))
+        .unwrap_or_default()
+});
+
+/// Number passed to a helper or the first one of a list, values missing in the image are `null`
+fn get_number(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Array(values) => values.first().and_then(serde_json::Value::as_f64),
+        value => value.as_f64(),
+    }
+}
+
+/// `manifest.toml` next to `main.svg` with the layout the template is designed for
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TemplateManifest {
+    pub left: Option<Vec<String>>,
+    pub center: Option<Vec<String>>,
+    pub right: Option<Vec<String>>,
+    /// Rows of the frame with their left, center and right groups
+    #[serde(rename = "row")]
+    pub rows: Vec<DisplayOrders>,
+    /// Font of the values used to measure the text, the width is estimated without it
+    pub font: Option<FontDescription>,
+}
+
 pub fn get_templates_path(template_name: &str) -> PathBuf {
     let config_dir = config_dir().unwrap();
     let dest_path = Path::new(&config_dir)
@@ -13,12 +172,20 @@ pub fn get_templates_path(template_name: &str) -> PathBuf {
     dest_path
 }
 
+/**
+ * Extracts default templates if they are missing
+ * Files added in newer versions are added without touching the existing ones
+ */
 pub fn init_templates_if_needed() -> Result<(), anyhow::Error> {
     let default_template_path = get_templates_path("default");
-    if default_template_path.exists() {
-        return Ok(());
+    fs::create_dir_all(&default_template_path)?;
+
+    for (name, content) in DEFAULT_TEMPLATE_FILES {
+        let path = default_template_path.join(name);
+        if !path.exists() {
+            fs::write(&path, content)?;
+        }
     }
-    copy_default_template()?;
     Ok(())
 }
 
@@ -31,39 +198,27 @@ pub fn copy_default_template() -> Result<(), anyhow::Error> {
     fs::create_dir_all(&default_template_path)?;
 
     // Copy the template files to the destination directory
-    let default_template_files = [
-        ("main.svg", include_str!("../templates/default/main.svg")),
-        (
-            "iso-icon.svg",
-            include_str!("../templates/default/iso-icon.svg"),
-        ),
-        (
-            "camera-icon.svg",
-            include_str!("../templates/default/camera-icon.svg"),
-        ),
-        (
-            "aperture-icon.svg",
-            include_str!("../templates/default/aperture-icon.svg"),
-        ),
-        (
-            "focal-length-icon.svg",
-            include_str!("../templates/default/focal-length-icon.svg"),
-        ),
-        (
-            "shutter-speed-icon.svg",
-            include_str!("../templates/default/shutter-speed-icon.svg"),
-        ),
-    ];
-
-    for (name, content) in default_template_files {
+    for (name, content) in DEFAULT_TEMPLATE_FILES {
         let path = default_template_path.join(name);
         fs::write(&path, content)?;
     }
     Ok(())
 }
 
+pub fn load_template_manifest(template_name: &str) -> Result<TemplateManifest, anyhow::Error> {
+    let manifest_path = get_templates_path(template_name).join("manifest.toml");
+    if !manifest_path.exists() {
+        return Ok(TemplateManifest::default());
+    }
+    let content = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("could not read template manifest `{:?}`", manifest_path))?;
+    toml::from_str(&content)
+        .with_context(|| format!("invalid template manifest `{:?}`", manifest_path))
+}
+
 pub fn register_templates(
     template_name: &str,
+    fields: &FieldRegistry,
     handlebars: &mut Handlebars,
 ) -> Result<(), anyhow::Error> {
     let templates_path = get_templates_path(template_name);
@@ -78,50 +233,60 @@ pub fn register_templates(
             )
         })?;
 
-    handlebars
-        .register_template_file("Camera", templates_path.join("camera-icon.svg"))
-        .with_context(|| {
-            format!(
-                "could not read template file`{:?}` in {:?}",
-                "camera-icon.svg".to_string(),
-                templates_path
-            )
-        })?;
+    // Icons are registered under the name of the field, fields without one get an empty partial.
+    // Icons missing in the template are expected to be removed by `FieldRegistry::remove_missing_icons`.
+    // Templates made for older versions use the PascalCase names like `DateTaken`
+    for field in fields.iter() {
+        let alias = to_pascal_case(&field.name);
+        register_icon(
+            handlebars,
+            &templates_path,
+            &field.name,
+            field.icon.as_deref(),
+        )?;
+        if alias != field.name && fields.get(&alias).is_none() {
+            register_icon(handlebars, &templates_path, &alias, field.icon.as_deref())?;
+        }
+    }
 
+    handlebars.register_helper("date", Box::new(date));
+    handlebars.register_helper("shutter_speed", Box::new(shutter_speed));
+    handlebars.register_helper("aperture", Box::new(aperture));
+    handlebars.register_helper("iso", Box::new(iso));
+    Ok(())
+}
+
+fn register_icon(
+    handlebars: &mut Handlebars,
+    templates_path: &Path,
+    name: &str,
+    file_name: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    let Some(file_name) = file_name else {
+        handlebars.register_partial(name, "")?;
+        return Ok(());
+    };
     handlebars
-        .register_template_file("Aperture", templates_path.join("aperture-icon.svg"))
-        .with_context(|| {
-            format!(
-                "could not read template file`{:?}`",
-                "aperture-icon.svg".to_string()
-            )
-        })?;
-    handlebars
-        .register_template_file(
-            "ShutterSpeed",
-            templates_path.join("shutter-speed-icon.svg"),
-        )
-        .with_context(|| {
-            format!(
-                "could not read template file`{:?}`",
-                "shutter-speed-icon.svg".to_string()
-            )
-        })?;
-    handlebars
-        .register_template_file("FocalLength", templates_path.join("focal-length-icon.svg"))
-        .with_context(|| {
-            format!(
-                "could not read template file`{:?}`",
-                "focal-length-icon.svg".to_string()
-            )
-        })?;
-    handlebars
-        .register_template_file("Iso", templates_path.join("iso-icon.svg"))
+        .register_template_file(name, templates_path.join(file_name))
         .with_context(|| {
             format!(
-                "could not read template file`{:?}`",
-                "iso-icon.svg".to_string()
+                "could not read template file`{:?}` in `{:?}`",
+                file_name.to_string(),
+                templates_path
             )
         })?;
     Ok(())
 }
+
+/// `date_taken` -> `DateTaken`
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
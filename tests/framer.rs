@@ -1,7 +1,11 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Result;
 use assert_cmd::Command;
+use tempfile::TempDir;
 
 #[test]
 fn generates_frame() -> Result<()> {
@@ -14,3 +18,559 @@ fn generates_frame() -> Result<()> {
     fs::remove_file("tests/assets/image_frame.svg").unwrap();
     Ok(())
 }
+
+/// Copies the image with EXIF to its own directory so the tests don't share generated frames
+fn copy_camera_image() -> Result<(TempDir, PathBuf)> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("camera.jpg");
+    fs::copy("tests/assets/camera.jpg", &path)?;
+    Ok((dir, path))
+}
+
+/// Writes a JPEG with the Nikon maker note storing the shutter count
+fn create_nikon_image() -> Result<(TempDir, PathBuf)> {
+    use exif::{experimental::Writer, Field, In, Tag, Value};
+    use std::io::Cursor;
+
+    // Type 3 maker note with its own TIFF header and a single ShutterCount entry
+    let mut maker_note = b"Nikon\0\x02\x10\0\0II*\0\x08\0\0\0\x01\0".to_vec();
+    maker_note.extend(0x00a7u16.to_le_bytes());
+    maker_note.extend(4u16.to_le_bytes());
+    maker_note.extend(1u32.to_le_bytes());
+    maker_note.extend(48213u32.to_le_bytes());
+    maker_note.extend(0u32.to_le_bytes());
+
+    let field = |tag, value| Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value,
+    };
+    let fields = [
+        field(Tag::Make, Value::Ascii(vec![b"NIKON CORPORATION".to_vec()])),
+        field(Tag::Model, Value::Ascii(vec![b"NIKON Z 6_2".to_vec()])),
+        field(Tag::MakerNote, Value::Undefined(maker_note, 0)),
+    ];
+    let mut writer = Writer::new();
+    fields.iter().for_each(|field| writer.push_field(field));
+    let mut tiff = Cursor::new(Vec::new());
+    writer.write(&mut tiff, false)?;
+    let tiff = tiff.into_inner();
+
+    // The APP1 segment with the EXIF goes before the segments of the original image
+    let image = fs::read("tests/assets/image.jpg")?;
+    let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe1];
+    jpeg.extend((tiff.len() as u16 + 8).to_be_bytes());
+    jpeg.extend(b"Exif\0\0");
+    jpeg.extend(tiff);
+    jpeg.extend(&image[2..]);
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("nikon.jpg");
+    fs::write(&path, jpeg)?;
+    Ok((dir, path))
+}
+
+#[test]
+fn displays_shutter_count() -> Result<()> {
+    let (dir, path) = create_nikon_image()?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", dir.path())
+        .arg("--show-maker-note")
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("nikon_frame.svg"))?;
+    assert!(frame.contains("Nikon Z 6II"));
+    assert!(frame.contains("48213"));
+
+    // Fujifilm stores only the image count
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = create_custom_template("<svg>[{{maker_note.ShutterCount}}]</svg>")?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "custom", "--show-maker-note"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("[]"));
+    Ok(())
+}
+
+#[test]
+fn shifts_capture_time_to_actual_time() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.args(["--show-date", "--date-format", "%d.%m.%Y %H:%M"])
+        .args(["--time-shift", "2024-07-14 20:00:09"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("14.07.2024 20:00"));
+    Ok(())
+}
+
+#[test]
+fn displays_equivalent_focal_length() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.args(["--focal-length", "both", "--show-equivalent-aperture"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("23mm (35mm)"));
+    assert!(frame.contains("f/4.3"));
+    Ok(())
+}
+
+#[test]
+fn displays_exposure_settings() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.arg("--show-exposure").arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("-⅓ EV"));
+    assert!(frame.contains("Aperture priority"));
+    assert!(frame.contains("Multi-segment"));
+    assert!(frame.contains("No flash"));
+    assert!(frame.contains("Auto WB"));
+    Ok(())
+}
+
+#[test]
+fn prefers_lens_from_xmp_sidecar() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    fs::write(
+        dir.path().join("camera.xmp"),
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+          <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description rdf:about="" xmlns:aux="http://ns.adobe.com/exif/1.0/aux/"
+                aux:Lens="Helios 44-2 58mm f/2"/>
+          </rdf:RDF>
+        </x:xmpmeta>"#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("Helios 44-2 58mm f/2"));
+    Ok(())
+}
+
+#[test]
+fn displays_signature() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.args(["--signature", "Photo {artist}, {year}"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("Photo Jane Doe, 2024"));
+    Ok(())
+}
+
+#[test]
+fn formats_exposure_values() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.args(["--aperture-format", "hook", "--iso-format", "prefix"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("1/250s"));
+    assert!(frame.contains("ƒ2.8"));
+    assert!(frame.contains("ISO 400"));
+    Ok(())
+}
+
+#[test]
+fn handles_missing_values() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("image.jpg");
+    fs::copy("tests/assets/image.jpg", &path)?;
+    let frame_path = dir.path().join("image_frame.svg");
+
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.args(["--missing-value", "hide"]).arg(&path);
+    cmd.assert().success();
+    let frame = fs::read_to_string(&frame_path)?;
+    assert!(!frame.contains("N/A"));
+    assert!(!frame.contains("<text"));
+
+    fs::remove_file(&frame_path)?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.args(["--missing-value", "error"]).arg(&path);
+    cmd.assert().success();
+    assert!(!frame_path.exists());
+    Ok(())
+}
+
+#[test]
+fn leaves_values_out_of_the_layout_unset() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = create_custom_template(
+        "<svg>[{{#if text_values.title}}title{{/if}}|{{text_values.rating}}]</svg>",
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "custom"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert_eq!(frame, "<svg>[|]</svg>");
+    Ok(())
+}
+
+#[test]
+fn applies_missing_value_policies_by_field_name() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = tempfile::tempdir()?;
+    fs::create_dir(config_dir.path().join("metaframer"))?;
+    fs::write(
+        config_dir.path().join("metaframer/config.toml"),
+        "[missing-values]\nshutter-count = \"error\"",
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--right", "shutter-count"])
+        .arg(&path);
+    cmd.assert().success();
+    assert!(!dir.path().join("camera_frame.svg").exists());
+
+    fs::write(
+        config_dir.path().join("metaframer/config.toml"),
+        "[missing-values]\nshuter-count = \"error\"",
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.env("XDG_CONFIG_HOME", config_dir.path()).arg(&path);
+    cmd.assert().failure();
+    Ok(())
+}
+
+#[test]
+fn overrides_values_from_sidecars() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    fs::write(
+        dir.path().join(".metaframer.toml"),
+        "camera = \"Pentax 67\"\nlens = \"SMC Takumar 105mm f/2.4\"",
+    )?;
+    fs::write(
+        dir.path().join("camera.metaframer.json"),
+        r#"{ "lens": "SMC Pentax 67 55mm f/4" }"#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.args(["--set", "iso=160"]).arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("Pentax 67"));
+    assert!(frame.contains("SMC Pentax 67 55mm f/4"));
+    assert!(frame.contains("160"));
+    assert!(!frame.contains("X-T30"));
+    Ok(())
+}
+
+#[test]
+fn displays_film_profile() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = tempfile::tempdir()?;
+    fs::create_dir(config_dir.path().join("metaframer"))?;
+    fs::write(
+        config_dir.path().join("metaframer/config.toml"),
+        r#"
+        [[film-profile]]
+        name = "Pentax 67 + Portra 400"
+        camera = "Pentax 67"
+        lens = "SMC Takumar 105mm f/2.4"
+        film-stock = "Kodak Portra 400"
+        iso = 400
+        focal-length = 105
+
+        [[camera-crop-factor]]
+        make = "Pentax"
+        model = "67"
+        crop-factor = 0.47
+        "#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--profile", "Pentax 67 + Portra 400"])
+        .args(["--focal-length", "both"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("Pentax 67"));
+    assert!(frame.contains("SMC Takumar 105mm f/2.4"));
+    assert!(frame.contains("Kodak Portra 400"));
+    assert!(frame.contains("105mm (49mm)"));
+    assert!(!frame.contains("X-T30"));
+    // Exposure of the scanner is left out
+    assert!(!frame.contains("f/2.8"));
+    assert!(!frame.contains("1/250s"));
+
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--profile", "Hasselblad"])
+        .arg(&path);
+    cmd.assert().failure();
+    Ok(())
+}
+
+#[test]
+fn arranges_configured_groups() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = tempfile::tempdir()?;
+    fs::create_dir_all(config_dir.path().join("metaframer/templates/default"))?;
+    fs::write(
+        config_dir
+            .path()
+            .join("metaframer/templates/default/manifest.toml"),
+        r#"center = ["lens"]"#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--left", "camera", "--right", "iso,aperture"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    let camera = frame.find("X-T30").unwrap();
+    let lens = frame.find("XF23mmF2").unwrap();
+    let iso = frame.find(">\n      400\n").unwrap();
+    let aperture = frame.find("f/2.8").unwrap();
+    assert!(camera < lens && lens < iso && iso < aperture);
+    assert!(!frame.contains("1/"));
+
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--left", "camera,shutter"])
+        .arg(&path);
+    cmd.assert().failure();
+    Ok(())
+}
+
+#[test]
+fn lays_out_rows() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = tempfile::tempdir()?;
+    fs::create_dir(config_dir.path().join("metaframer"))?;
+    fs::write(
+        config_dir.path().join("metaframer/config.toml"),
+        r#"
+        [[row]]
+        left = ["camera", "lens"]
+
+        [[row]]
+        right = ["aperture", "shutter_speed", "iso"]
+        "#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--height", "50"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains(r#"height="100""#));
+    let lens = frame.find("XF23mmF2").unwrap();
+    let aperture = frame.find("f/2.8").unwrap();
+    assert!(lens < aperture);
+    // Text of the rows is centered 50 pixels apart
+    assert!(frame[..lens].contains(r#"y="31""#));
+    assert!(frame[lens..aperture].contains(r#"y="81""#));
+    Ok(())
+}
+
+#[test]
+fn renders_template_without_icons() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = tempfile::tempdir()?;
+    let template_dir = config_dir.path().join("metaframer/templates/bare");
+    fs::create_dir_all(&template_dir)?;
+    fs::copy("templates/default/main.svg", template_dir.join("main.svg"))?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "bare"])
+        .arg(&path);
+    cmd.assert().success();
+
+    // The camera starts at the outer border without the space for the icon
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains("X-T30"));
+    assert!(frame.contains(r#"<text x="10""#));
+    Ok(())
+}
+
+#[test]
+fn loads_geonames_only_for_location() -> Result<()> {
+    let (_dir, path) = copy_camera_image()?;
+    let config_dir = tempfile::tempdir()?;
+    fs::create_dir(config_dir.path().join("metaframer"))?;
+    fs::write(
+        config_dir.path().join("metaframer/config.toml"),
+        r#"geonames = "/nonexistent/cities500.txt""#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path()).arg(&path);
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .arg("--show-location")
+        .arg(&path);
+    cmd.assert().failure();
+    Ok(())
+}
+
+/// Writes `main.svg` of the `custom` template into a new configuration directory
+fn create_custom_template(main: &str) -> Result<TempDir> {
+    let config_dir = tempfile::tempdir()?;
+    let template_dir = config_dir.path().join("metaframer/templates/custom");
+    fs::create_dir_all(&template_dir)?;
+    fs::write(template_dir.join("main.svg"), main)?;
+    Ok(config_dir)
+}
+
+#[test]
+fn renders_partials_of_older_templates() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = create_custom_template("<svg>[{{> Camera}}|{{> DateTaken}}]</svg>")?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "custom"])
+        .arg(&path);
+    cmd.assert().success();
+    assert!(dir.path().join("camera_frame.svg").exists());
+
+    fs::write(
+        config_dir.path().join("metaframer/config.toml"),
+        r#"
+        [[field]]
+        name = "main"
+        exif = "Software"
+        "#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "custom"])
+        .arg(&path);
+    cmd.assert().failure();
+    Ok(())
+}
+
+#[test]
+fn excludes_wrapped_rows_from_image_height() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", dir.path())
+        .args(["--resolution", "720p", "--overflow", "wrap"])
+        .args(["--show-exposure", "--show-date"])
+        .arg(&path);
+    cmd.assert().success();
+
+    // The 3:2 image is scaled to the 720px height without both of the rows
+    let frame = fs::read_to_string(dir.path().join("camera_frame.svg"))?;
+    assert!(frame.contains(r#"viewBox="0 0 960 80""#));
+
+    // Three rows of 250px don't fit into the 720px height
+    fs::remove_file(dir.path().join("camera_frame.svg"))?;
+    fs::write(
+        dir.path().join("metaframer/config.toml"),
+        r#"
+        [[row]]
+        left = ["camera"]
+
+        [[row]]
+        left = ["lens"]
+
+        [[row]]
+        left = ["iso"]
+        "#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+    cmd.env("XDG_CONFIG_HOME", dir.path())
+        .args(["--resolution", "720p", "--height", "250"])
+        .arg(&path);
+    cmd.assert().success();
+    assert!(!dir.path().join("camera_frame.svg").exists());
+    Ok(())
+}
+
+#[test]
+fn formats_missing_date_as_empty() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("image.jpg");
+    fs::copy("tests/assets/image.jpg", &path)?;
+    let config_dir = create_custom_template(r#"<svg>[{{date date_taken "%Y"}}]</svg>"#)?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "custom"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("image_frame.svg"))?;
+    assert_eq!(frame, "<svg>[]</svg>");
+    Ok(())
+}
+
+#[test]
+fn leaves_no_frame_when_render_fails() -> Result<()> {
+    let (dir, path) = copy_camera_image()?;
+    let config_dir = create_custom_template("<svg>{{undefined_helper width}}</svg>")?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "custom"])
+        .arg(&path);
+    cmd.assert().success();
+
+    assert!(!dir.path().join("camera_frame.svg").exists());
+    Ok(())
+}
+
+#[test]
+fn formats_missing_exposure_as_empty() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("image.jpg");
+    fs::copy("tests/assets/image.jpg", &path)?;
+    let config_dir = create_custom_template(
+        r#"<svg>[{{shutter_speed exif.ExposureTime.raw "compact"}}|{{aperture exif.FNumber.raw "hook"}}|{{iso exif.PhotographicSensitivity.raw "prefix"}}]</svg>"#,
+    )?;
+    let mut cmd = Command::cargo_bin("metaframer")?;
+
+    cmd.env("XDG_CONFIG_HOME", config_dir.path())
+        .args(["--template", "custom"])
+        .arg(&path);
+    cmd.assert().success();
+
+    let frame = fs::read_to_string(dir.path().join("image_frame.svg"))?;
+    assert_eq!(frame, "<svg>[||]</svg>");
+    Ok(())
+}